@@ -12,3 +12,12 @@ pub struct GridWorldCellBundle {
     pub transform: Transform,
     pub global_transform: GlobalTransform,
 }
+
+/// Food component bundle
+#[derive(Bundle)]
+pub struct FoodBundle {
+    pub food: Food,
+
+    pub transform: Transform,
+    pub global_transform: GlobalTransform,
+}
@@ -8,6 +8,8 @@ use crate::util::*;
 
 const PADDING: f32 = 0.1;
 
+const FOOD_SCALE: f32 = 0.4;
+
 /// GridWorld Cell tag
 #[derive(Debug, Default, Component, Inspectable)]
 pub struct GridWorldCell;
@@ -41,3 +43,59 @@ impl GridWorldCell {
         });
     }
 }
+
+/// Food state
+#[derive(Debug, Default, Component, Inspectable)]
+pub struct Food {
+    /// The GridWorld cell the food is on
+    pub cell: UVec2,
+}
+
+impl Food {
+    pub fn spawn(commands: &mut Commands, cell: UVec2, color: Color) -> Entity {
+        let position = cell_position(cell, 0.5);
+        debug!("Spawning food at {}", cell);
+
+        commands
+            .spawn_bundle(FoodBundle {
+                food: Food { cell },
+                transform: Transform::from_translation(position),
+                global_transform: GlobalTransform::default(),
+            })
+            .insert(Name::new(format!("Food {}", cell)))
+            .with_children(|parent| {
+                parent.spawn_bundle(SpriteBundle {
+                    sprite: Sprite {
+                        color,
+                        custom_size: Some(Vec2::new(
+                            crate::CELL_WIDTH * FOOD_SCALE,
+                            crate::CELL_HEIGHT * FOOD_SCALE,
+                        )),
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                });
+            })
+            .id()
+    }
+
+    /// Spawns and despawns food entities to match the food cells
+    pub fn sync(
+        commands: &mut Commands,
+        query: &Query<(Entity, &Food)>,
+        cells: &[UVec2],
+        color: Color,
+    ) {
+        for (entity, food) in query.iter() {
+            if !cells.contains(&food.cell) {
+                commands.entity(entity).despawn_recursive();
+            }
+        }
+
+        for cell in cells {
+            if !query.iter().any(|(_, food)| food.cell == *cell) {
+                Food::spawn(commands, *cell, color);
+            }
+        }
+    }
+}
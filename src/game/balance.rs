@@ -0,0 +1,115 @@
+//! Game balance values
+
+use bevy::reflect::TypeUuid;
+use serde::{Deserialize, Serialize};
+
+use super::gridworld::*;
+use super::persist::*;
+
+/// Game balance values, loaded from the balance asset
+///
+/// Missing values fall back to the defaults
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, TypeUuid)]
+#[uuid = "61980218-b1c8-49f6-bd60-fc340a3bbb6c"]
+#[serde(default)]
+pub struct Balance {
+    /// Base automata health
+    pub base_health: isize,
+
+    /// Constitution modifier for calculating initial health
+    pub constitution_mod: f32,
+
+    /// Base automata movement
+    pub base_movement: isize,
+
+    /// Dexterity modifier for calculating movement
+    pub dexterity_mod: f32,
+
+    /// Base automata attack damage
+    pub base_attack: isize,
+
+    /// Strength modifier for calculating attack damage
+    pub strength_mod: f32,
+
+    /// Base automata attack damage absorb
+    pub base_attack_absorb: isize,
+
+    /// Fortitude modifier for calculating damage absorb
+    pub fortitude_mod: f32,
+
+    /// Base chance to move towards the enemy automata
+    pub base_move_towards_enemy: f64,
+
+    /// Aggression modifier for calculating chance to move towards enemy automata
+    pub aggression_mod: f64,
+
+    /// Base chance to move towards food
+    pub base_move_towards_food: f64,
+
+    /// Intellect modifier for calculating chance to move towards food
+    pub intellect_mod: f64,
+
+    /// Maximum chance to move towards the enemy automata or food
+    pub max_move_chance: f64,
+
+    /// Number of stat points each automata has
+    pub stat_points: isize,
+
+    /// Number of rounds in a game
+    pub rounds: usize,
+
+    /// Bounds of the AI population mutation rate
+    /// as it adapts to the population diversity
+    pub min_mutation_rate: f64,
+    pub max_mutation_rate: f64,
+
+    /// Fraction of the total points a mutation may move a stat by
+    pub mutation_strength: f32,
+
+    /// Food spawn settings
+    pub food: FoodSettings,
+}
+
+impl Default for Balance {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+impl Persist for Balance {}
+
+impl Balance {
+    /// The built in balance
+    pub const DEFAULT: Balance = Balance {
+        base_health: 10,
+        constitution_mod: 1.0,
+        base_movement: 1,
+        dexterity_mod: 0.2,
+        base_attack: 1,
+        strength_mod: 0.25,
+        base_attack_absorb: 0,
+        fortitude_mod: 0.25,
+        base_move_towards_enemy: 0.1, // 10% starting chance
+        aggression_mod: 0.05 / 5.0,   // 5% chance every 5 points
+        base_move_towards_food: 0.1,
+        intellect_mod: 0.05 / 5.0, // 5% chance every 5 points
+        max_move_chance: 0.75,
+        stat_points: 50,
+        rounds: 10,
+        min_mutation_rate: 0.005, // 0.5% chance to mutate
+        max_mutation_rate: 0.25,
+        mutation_strength: 0.2,
+        food: FoodSettings::DEFAULT,
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn asset_matches_default() {
+        let balance = Balance::from_str(include_str!("../../assets/game.balance.ron"), Format::Ron);
+        assert_eq!(balance.unwrap(), Balance::DEFAULT);
+    }
+}
@@ -0,0 +1,214 @@
+//! Neural network automata controller
+
+use bevy::math::UVec2;
+use serde::{Deserialize, Serialize};
+
+use crate::resources::*;
+
+/// Network inputs: enemy offset, nearest food offset, own health, enemy health
+pub const BRAIN_INPUTS: usize = 6;
+
+/// Network hidden layer size
+pub const BRAIN_HIDDEN: usize = 8;
+
+/// Network outputs: move left, right, down, up and attack
+pub const BRAIN_OUTPUTS: usize = 5;
+
+/// Number of weights, including the bias weights
+const BRAIN_WEIGHTS: usize = (BRAIN_INPUTS + 1) * BRAIN_HIDDEN + (BRAIN_HIDDEN + 1) * BRAIN_OUTPUTS;
+
+/// Standard deviation of the initial weights
+const INITIAL_WEIGHT_STD_DEV: f32 = 1.0;
+
+/// What the automata can see
+#[derive(Debug, Clone, Copy)]
+pub struct BrainInputs {
+    /// Enemy offset relative to the grid size, in the range [-1..1]
+    pub enemy: (f32, f32),
+
+    /// Nearest food offset relative to the grid size, zero if there is no food
+    pub food: (f32, f32),
+
+    /// Health relative to initial health, in the range [0..1]
+    pub health: f32,
+    pub enemy_health: f32,
+}
+
+/// Movement direction
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BrainDirection {
+    Left,
+    Right,
+    Down,
+    Up,
+}
+
+impl BrainDirection {
+    const ALL: [BrainDirection; 4] = [
+        BrainDirection::Left,
+        BrainDirection::Right,
+        BrainDirection::Down,
+        BrainDirection::Up,
+    ];
+
+    /// Gets the neighbor of a cell in this direction, None if it is out of bounds
+    pub fn step(&self, cell: UVec2, width: usize, height: usize) -> Option<UVec2> {
+        match self {
+            BrainDirection::Left if cell.x > 0 => Some(UVec2::new(cell.x - 1, cell.y)),
+            BrainDirection::Right if (cell.x as usize) < width - 1 => {
+                Some(UVec2::new(cell.x + 1, cell.y))
+            }
+            BrainDirection::Down if cell.y > 0 => Some(UVec2::new(cell.x, cell.y - 1)),
+            BrainDirection::Up if (cell.y as usize) < height - 1 => {
+                Some(UVec2::new(cell.x, cell.y + 1))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// What the automata decided to do
+#[derive(Debug, Clone, Copy)]
+pub struct BrainDecision {
+    pub direction: BrainDirection,
+
+    /// The chosen direction output activation
+    pub confidence: f32,
+
+    pub attack: bool,
+}
+
+/// A small feed-forward network with a single tanh hidden layer
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "BrainWeights")]
+pub struct Brain {
+    weights: Vec<f32>,
+}
+
+/// Serialized brain weights, checked before they become a Brain
+#[derive(Deserialize)]
+#[serde(rename = "Brain")]
+struct BrainWeights {
+    weights: Vec<f32>,
+}
+
+impl TryFrom<BrainWeights> for Brain {
+    type Error = String;
+
+    fn try_from(brain: BrainWeights) -> Result<Self, Self::Error> {
+        if brain.weights.len() != BRAIN_WEIGHTS {
+            return Err(format!(
+                "Brain has {} weights, expected {}",
+                brain.weights.len(),
+                BRAIN_WEIGHTS
+            ));
+        }
+
+        Ok(Self {
+            weights: brain.weights,
+        })
+    }
+}
+
+impl Brain {
+    /// Creates a new brain with random weights
+    pub fn random(random: &mut Random) -> Self {
+        let weights = (0..BRAIN_WEIGHTS)
+            .map(|_| random.normal(0.0, INITIAL_WEIGHT_STD_DEV))
+            .collect();
+
+        Self { weights }
+    }
+
+    /// Gets the network weights
+    #[inline]
+    pub fn weights(&self) -> &[f32] {
+        &self.weights
+    }
+
+    /// Runs the network
+    pub fn decide(&self, inputs: &BrainInputs) -> BrainDecision {
+        let inputs = [
+            inputs.enemy.0,
+            inputs.enemy.1,
+            inputs.food.0,
+            inputs.food.1,
+            inputs.health,
+            inputs.enemy_health,
+        ];
+
+        let (hidden_weights, output_weights) =
+            self.weights.split_at((BRAIN_INPUTS + 1) * BRAIN_HIDDEN);
+
+        let hidden = layer(&inputs, hidden_weights);
+        let outputs = layer(&hidden, output_weights);
+
+        let (idx, confidence) = outputs[..BrainDirection::ALL.len()]
+            .iter()
+            .copied()
+            .enumerate()
+            .max_by(|a, b| a.1.total_cmp(&b.1))
+            .unwrap();
+
+        BrainDecision {
+            direction: BrainDirection::ALL[idx],
+            confidence,
+            attack: outputs[BrainDirection::ALL.len()] > 0.0,
+        }
+    }
+
+    /// Creates a child brain with each weight from a random parent
+    pub fn crossover(&self, partner: &Brain, random: &mut Random) -> Brain {
+        let weights = self
+            .weights
+            .iter()
+            .zip(partner.weights.iter())
+            .map(|(a, b)| if random.coin() { *a } else { *b })
+            .collect();
+
+        Self { weights }
+    }
+
+    /// Randomly nudges weights at the given mutation rate
+    ///
+    /// Strength is the standard deviation of the nudge
+    pub fn mutate(&mut self, mutation_rate: f64, mutation_strength: f32, random: &mut Random) {
+        for weight in self.weights.iter_mut() {
+            if random.random() < mutation_rate {
+                *weight += random.normal(0.0, mutation_strength);
+            }
+        }
+    }
+}
+
+/// Runs a fully connected tanh layer
+///
+/// Each neuron has a weight for each input followed by a bias weight
+fn layer(inputs: &[f32], weights: &[f32]) -> Vec<f32> {
+    weights
+        .chunks(inputs.len() + 1)
+        .map(|neuron| {
+            let (bias, neuron) = neuron.split_last().unwrap();
+            let sum = neuron
+                .iter()
+                .zip(inputs.iter())
+                .map(|(weight, input)| weight * input)
+                .sum::<f32>();
+            (sum + bias).tanh()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn weight_count_is_validated() {
+        let brain = Brain::random(&mut Random::new(0));
+        let data = ron::to_string(&brain).unwrap();
+        assert_eq!(ron::from_str::<Brain>(&data).unwrap(), brain);
+
+        assert!(ron::from_str::<Brain>("(weights: [1.0, 2.0])").is_err());
+    }
+}
@@ -2,37 +2,138 @@
 
 use bevy::prelude::*;
 use bevy_inspector_egui::prelude::*;
+use serde::{Deserialize, Serialize};
 
 use crate::resources::*;
 
+use super::brain::*;
+use super::persist::*;
+use super::simulation::*;
 use super::stats::*;
 
-pub const MUTATION_RATE: f64 = 0.01; // 1% chance to mutate
+/// Number of the fittest DNA copied unchanged into the next generation
+pub const ELITE_COUNT: usize = 2;
 
-#[derive(Debug, Copy, Clone)]
-enum CrossoverMethod {
-    #[allow(dead_code)]
+/// Stat distance within which DNA belongs to the same species
+pub const SPECIES_DISTANCE: isize = 20;
+
+/// Number of candidates in a selection tournament
+pub const TOURNAMENT_SIZE: usize = 3;
+
+/// Genetic crossover method
+///
+/// Genes are taken in stat registry order, children are always
+/// renormalized to the DNA points budget
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CrossoverMethod {
+    /// First half of the genes from one parent, the rest from the other
     Midpoint,
 
-    #[allow(dead_code)]
+    /// Each gene from a random parent
     Coin,
+
+    /// Genes from one parent up to a random cut, the rest from the other
+    SinglePoint,
+
+    /// Genes alternate between parents at a number of random cuts
+    MultiPoint(usize),
+
+    /// Genes are a random weighted average of both parents
+    Blend,
+
+    /// Each gene from a random parent, in a random order,
+    /// limited to the points that are left in the budget
+    Uniform,
 }
 
-#[derive(Debug, Inspectable, Default)]
-struct StatSetFitness {
-    constitution: f32,
-    dexterity: f32,
-    strength: f32,
-    fortitude: f32,
-    aggression: f32,
-    intellect: f32,
+/// Parent selection method
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SelectionMethod {
+    /// Fitness proportionate selection
+    Roulette,
+
+    /// Fittest of a number of random candidates
+    Tournament(usize),
+
+    /// Selection proportionate to fitness rank
+    Rank,
+
+    /// NSGA-II binary tournament on Pareto rank and crowding distance
+    Pareto,
+}
+
+impl StatSetFitness {
+    /// Number of fitness objectives, one for each stat
+    pub const OBJECTIVES: usize = StatId::ALL.len();
+
+    /// Gets each fitness term as an objective to maximize
+    pub fn objectives(&self) -> [f32; Self::OBJECTIVES] {
+        StatId::ALL.map(|statid| self.value(statid))
+    }
+
+    /// Checks if this fitness Pareto dominates another
+    ///
+    /// It must be no worse in any objective and better in at least one
+    pub fn dominates(&self, other: &StatSetFitness) -> bool {
+        let mut better = false;
+        for (a, b) in self.objectives().iter().zip(other.objectives().iter()) {
+            if a < b {
+                return false;
+            }
+            better |= a > b;
+        }
+        better
+    }
+
+    /// Measures fitness from round results
+    pub fn new(health: usize, metrics: &MatchMetrics) -> Self {
+        let mut fitness = Self::default();
+        for definition in STATS {
+            fitness.set_value(definition.id, (definition.fitness)(health, metrics));
+        }
+        fitness
+    }
+
+    /// Averages the fitness of several rounds
+    pub fn mean(fitnesses: &[StatSetFitness]) -> Self {
+        let mut mean = Self::default();
+        if fitnesses.is_empty() {
+            return mean;
+        }
+
+        let count = fitnesses.len() as f32;
+        for statid in StatId::ALL {
+            let total = fitnesses
+                .iter()
+                .map(|fitness| fitness.value(statid))
+                .sum::<f32>();
+            mean.set_value(statid, total / count);
+        }
+
+        mean
+    }
+
+    /// Gets the weighted total fitness
+    pub fn total(&self) -> f32 {
+        STATS
+            .iter()
+            .map(|definition| self.value(definition.id) * definition.fitness_weight)
+            .sum()
+    }
 }
 
+impl Persist for Dna {}
+
 /// Genetic algorithm DNA
-#[derive(Debug, Inspectable)]
+#[derive(Debug, Clone, Inspectable, Serialize, Deserialize)]
 pub struct Dna {
     genes: StatSet,
 
+    /// Optional neural network controller
+    #[inspectable(ignore)]
+    #[serde(default)]
+    brain: Option<Brain>,
+
     fitness: StatSetFitness,
     rounds: usize,
     points: isize,
@@ -43,106 +144,192 @@ impl Dna {
     pub fn new(rounds: usize, points: isize, random: &mut Random) -> Self {
         Self {
             genes: StatSet::random(points, random),
+            brain: None,
             fitness: StatSetFitness::default(),
             rounds,
             points,
         }
     }
 
-    /// Adjust genetic fitness based on round results
-    pub fn fitness(&mut self, stats: &StatSet, health: usize) {
-        self.fitness.constitution = health.pow(2) as f32 / stats.initial_health().pow(2) as f32;
+    /// Creates a new, randomized DNA with a neural network controller
+    pub fn with_brain(rounds: usize, points: isize, random: &mut Random) -> Self {
+        let mut dna = Self::new(rounds, points, random);
+        dna.brain = Some(Brain::random(random));
+        dna
+    }
+
+    /// Gets the neural network controller, if there is one
+    #[inline]
+    pub fn brain(&self) -> Option<&Brain> {
+        self.brain.as_ref()
+    }
 
-        // TODO:
-        //self.fitness.dexterity = ???
-        //self.fitness.strength = ???
-        //self.fitenss.fortitude = ???
-        //self.fitness.aggression = ???
-        //self.fitness.intellect = ???
+    /// Gets the genes
+    #[inline]
+    pub fn genes(&self) -> &StatSet {
+        &self.genes
+    }
+
+    /// Gets the per-stat fitness
+    #[inline]
+    pub fn stat_fitness(&self) -> &StatSetFitness {
+        &self.fitness
+    }
+
+    /// Gets the weighted total fitness
+    #[inline]
+    pub fn total_fitness(&self) -> f32 {
+        self.fitness.total()
+    }
+
+    /// Sets the genetic fitness
+    #[inline]
+    pub fn set_fitness(&mut self, fitness: StatSetFitness) {
+        self.fitness = fitness;
     }
 
     /// Create a child through gentics crossover
-    fn crossover(&self, partner: &Dna, method: CrossoverMethod, random: &mut Random) -> Dna {
-        let mut child = Dna::new(self.rounds, self.points, random);
+    pub fn crossover(&self, partner: &Dna, method: CrossoverMethod, random: &mut Random) -> Dna {
+        let mut genes = StatSet::default();
 
         match method {
             CrossoverMethod::Midpoint => {
-                child.genes.set_constitution(self.genes.constitution());
-                child.genes.set_dexterity(self.genes.dexterity());
-                child.genes.set_strength(self.genes.strength());
-                child.genes.set_fortitude(partner.genes.fortitude());
-                child.genes.set_aggression(partner.genes.aggression());
-                child.genes.set_intellect(partner.genes.intellect());
+                let cut = StatId::ALL.len() / 2;
+                self.crossover_cuts(partner, &mut genes, &[cut]);
             }
             CrossoverMethod::Coin => {
-                child
-                    .genes
-                    .set_constitution(if random.random_range(0..=1) == 0 {
-                        self.genes.constitution()
-                    } else {
-                        partner.genes.constitution()
-                    });
-
-                child
-                    .genes
-                    .set_dexterity(if random.random_range(0..=1) == 0 {
-                        self.genes.dexterity()
-                    } else {
-                        partner.genes.dexterity()
-                    });
-
-                child
-                    .genes
-                    .set_strength(if random.random_range(0..=1) == 0 {
-                        self.genes.strength()
-                    } else {
-                        partner.genes.strength()
-                    });
-
-                child
-                    .genes
-                    .set_fortitude(if random.random_range(0..=1) == 0 {
-                        self.genes.fortitude()
-                    } else {
-                        partner.genes.fortitude()
-                    });
-
-                child
-                    .genes
-                    .set_aggression(if random.random_range(0..=1) == 0 {
-                        self.genes.aggression()
-                    } else {
-                        partner.genes.aggression()
-                    });
-
-                child
-                    .genes
-                    .set_intellect(if random.random_range(0..=1) == 0 {
-                        self.genes.intellect()
-                    } else {
-                        partner.genes.intellect()
-                    });
+                for statid in StatId::ALL {
+                    let parent = if random.coin() { self } else { partner };
+                    genes.set_value(statid, parent.genes.value(statid));
+                }
+            }
+            CrossoverMethod::SinglePoint => {
+                let cut = random.random_range(1..StatId::ALL.len());
+                self.crossover_cuts(partner, &mut genes, &[cut]);
+            }
+            CrossoverMethod::MultiPoint(count) => {
+                let mut cuts = (1..StatId::ALL.len()).collect::<Vec<_>>();
+                random.shuffle(&mut cuts);
+                cuts.truncate(count.max(1));
+                cuts.sort_unstable();
+                self.crossover_cuts(partner, &mut genes, &cuts);
+            }
+            CrossoverMethod::Blend => {
+                let alpha = random.random();
+                for statid in StatId::ALL {
+                    let value = alpha * self.genes.value(statid) as f64
+                        + (1.0 - alpha) * partner.genes.value(statid) as f64;
+                    genes.set_value(statid, value.round() as isize);
+                }
+            }
+            CrossoverMethod::Uniform => {
+                let mut statids = StatId::ALL.to_vec();
+                random.shuffle(&mut statids);
+
+                let mut remaining = self.points;
+                for statid in statids {
+                    let parent = if random.coin() { self } else { partner };
+                    let value = parent.genes.value(statid).clamp(0, remaining.max(0));
+                    genes.set_value(statid, value);
+                    remaining -= value;
+                }
             }
         }
 
-        child
+        genes.normalize(self.points, random);
+
+        let brain = match (&self.brain, &partner.brain) {
+            (Some(brain), Some(partner)) => Some(brain.crossover(partner, random)),
+            (Some(brain), None) | (None, Some(brain)) => Some(brain.clone()),
+            (None, None) => None,
+        };
+
+        Dna {
+            genes,
+            brain,
+            fitness: StatSetFitness::default(),
+            rounds: self.rounds,
+            points: self.points,
+        }
+    }
+
+    /// Copies genes into the child, switching parents at each cut
+    fn crossover_cuts(&self, partner: &Dna, genes: &mut StatSet, cuts: &[usize]) {
+        let mut from_self = true;
+        for (idx, statid) in StatId::ALL.into_iter().enumerate() {
+            if cuts.contains(&idx) {
+                from_self = !from_self;
+            }
+
+            let parent = if from_self { self } else { partner };
+            genes.set_value(statid, parent.genes.value(statid));
+        }
     }
 
     /// Randomly mutate a specific stat at the given mutation rate
-    fn mutate_stat(&mut self, mutation_rate: f64, random: &mut Random, statid: StatId) {
+    fn mutate_stat(
+        &mut self,
+        mutation_rate: f64,
+        mutation_strength: f32,
+        random: &mut Random,
+        statid: StatId,
+    ) {
         if random.random() < mutation_rate {
             debug!("{} mutation!", statid.name());
-            self.genes.randomize_stat(statid);
+            self.genes.randomize_stat(statid, mutation_strength, random);
+        }
+    }
+
+    /// Mutate random genes at the given mutation rate and strength
+    pub fn mutate(&mut self, mutation_rate: f64, mutation_strength: f32, random: &mut Random) {
+        for statid in StatId::ALL {
+            self.mutate_stat(mutation_rate, mutation_strength, random, statid);
+        }
+
+        if let Some(brain) = &mut self.brain {
+            brain.mutate(mutation_rate, mutation_strength, random);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::balance::*;
+    use super::super::stats::tests::*;
+    use super::*;
+
+    #[test]
+    fn crossover_keeps_budget() {
+        let methods = [
+            CrossoverMethod::Midpoint,
+            CrossoverMethod::Coin,
+            CrossoverMethod::SinglePoint,
+            CrossoverMethod::MultiPoint(1),
+            CrossoverMethod::MultiPoint(2),
+            CrossoverMethod::MultiPoint(StatId::ALL.len()),
+            CrossoverMethod::Blend,
+            CrossoverMethod::Uniform,
+        ];
+
+        let mut random = Random::new(0);
+        for method in methods {
+            for _ in 0..100 {
+                let a = Dna::new(1, POINTS, &mut random);
+                let b = Dna::new(1, POINTS, &mut random);
+                assert_budget(a.crossover(&b, method, &mut random).genes());
+            }
         }
     }
 
-    /// Mutate random genes at the given mutation rate
-    fn mutate(&mut self, mutation_rate: f64, random: &mut Random) {
-        self.mutate_stat(mutation_rate, random, StatId::Constitution);
-        self.mutate_stat(mutation_rate, random, StatId::Dexterity);
-        self.mutate_stat(mutation_rate, random, StatId::Strength);
-        self.mutate_stat(mutation_rate, random, StatId::Fortitude);
-        self.mutate_stat(mutation_rate, random, StatId::Aggression);
-        self.mutate_stat(mutation_rate, random, StatId::Intellect);
+    #[test]
+    fn mutate_keeps_budget() {
+        let mut random = Random::new(0);
+        for strength in [Balance::DEFAULT.mutation_strength, 1.0] {
+            for _ in 0..100 {
+                let mut dna = Dna::new(1, POINTS, &mut random);
+                dna.mutate(1.0, strength, &mut random);
+                assert_budget(dna.genes());
+            }
+        }
     }
 }
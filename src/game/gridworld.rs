@@ -0,0 +1,214 @@
+//! GridWorld geometry and food spawning
+
+use bevy::math::UVec2;
+use serde::{Deserialize, Serialize};
+
+use crate::resources::*;
+
+/// A GridWorld cell
+#[derive(Debug)]
+pub struct Cell(pub UVec2);
+
+/// The grid... world
+#[derive(Debug, Default)]
+pub struct GridWorld {
+    width: usize,
+    height: usize,
+
+    pub cells: Vec<Cell>,
+}
+
+impl GridWorld {
+    /// Creates a new grid world
+    pub fn new(width: usize, height: usize) -> Self {
+        let size = width * height;
+        let mut cells = Vec::with_capacity(size);
+
+        for y in 0..height {
+            for x in 0..width {
+                cells.push(Cell(UVec2::new(x as u32, y as u32)));
+            }
+        }
+
+        Self {
+            width,
+            height,
+            cells,
+        }
+    }
+
+    /// Gets the width of the grid in cells
+    #[inline]
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    /// Gets the height of the grid in cells
+    #[inline]
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Gets the in-bounds orthogonal neighbors of a cell
+    pub fn neighbors(&self, cell: UVec2) -> Vec<UVec2> {
+        let mut neighbors = Vec::with_capacity(4);
+
+        if cell.x > 0 {
+            neighbors.push(UVec2::new(cell.x - 1, cell.y));
+        }
+
+        if (cell.x as usize) < self.width - 1 {
+            neighbors.push(UVec2::new(cell.x + 1, cell.y));
+        }
+
+        if cell.y > 0 {
+            neighbors.push(UVec2::new(cell.x, cell.y - 1));
+        }
+
+        if (cell.y as usize) < self.height - 1 {
+            neighbors.push(UVec2::new(cell.x, cell.y + 1));
+        }
+
+        neighbors
+    }
+
+    /// Gets a random neighbor of a cell
+    pub fn random_neighbor(&self, cell: UVec2, random: &mut Random) -> UVec2 {
+        let neighbors = self.neighbors(cell);
+        if neighbors.is_empty() {
+            return cell;
+        }
+
+        neighbors[random.random_range(0..neighbors.len())]
+    }
+
+    /// Gets a random cell that isn't occupied
+    pub fn random_free_cell(&self, occupied: &[UVec2], random: &mut Random) -> Option<UVec2> {
+        let free = self
+            .cells
+            .iter()
+            .map(|cell| cell.0)
+            .filter(|cell| !occupied.contains(cell))
+            .collect::<Vec<_>>();
+        if free.is_empty() {
+            return None;
+        }
+
+        Some(free[random.random_range(0..free.len())])
+    }
+
+    /// Gets the neighbor of a cell that is one step closer to the target
+    ///
+    /// Steps along the axis with the greatest distance,
+    /// breaking ties randomly
+    pub fn step_towards(&self, cell: UVec2, target: UVec2, random: &mut Random) -> UVec2 {
+        let dx = target.x as i64 - cell.x as i64;
+        let dy = target.y as i64 - cell.y as i64;
+        if dx == 0 && dy == 0 {
+            return cell;
+        }
+
+        let horizontal = match dx.abs().cmp(&dy.abs()) {
+            std::cmp::Ordering::Greater => true,
+            std::cmp::Ordering::Less => false,
+            std::cmp::Ordering::Equal => random.coin(),
+        };
+
+        if horizontal {
+            UVec2::new((cell.x as i64 + dx.signum()) as u32, cell.y)
+        } else {
+            UVec2::new(cell.x, (cell.y as i64 + dy.signum()) as u32)
+        }
+    }
+}
+
+/// Food spawn settings
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FoodSettings {
+    /// Amount of food spawned at the start of a round
+    pub initial: usize,
+
+    /// Maximum amount of food in the GridWorld at once
+    pub max: usize,
+
+    /// Number of actions between food respawns
+    pub respawn_actions: usize,
+
+    /// Amount of health restored by eating food
+    pub health: usize,
+}
+
+impl Default for FoodSettings {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+impl FoodSettings {
+    /// The built in food settings
+    pub const DEFAULT: FoodSettings = FoodSettings {
+        initial: 3,
+        max: 5,
+        respawn_actions: 8,
+        health: 3,
+    };
+}
+
+/// Food spawner
+#[derive(Debug)]
+pub struct FoodSpawner {
+    settings: FoodSettings,
+
+    started: bool,
+    countdown: usize,
+}
+
+impl FoodSpawner {
+    /// Creates a new food spawner
+    pub fn new(settings: FoodSettings) -> Self {
+        Self {
+            settings,
+            started: false,
+            countdown: settings.respawn_actions,
+        }
+    }
+
+    /// Gets the amount of health restored by eating food
+    #[inline]
+    pub fn health(&self) -> usize {
+        self.settings.health
+    }
+
+    /// Advances the spawner by one action
+    ///
+    /// Returns the amount of food that should be spawned
+    pub fn tick(&mut self, current: usize) -> usize {
+        if !self.started {
+            self.started = true;
+            return self
+                .settings
+                .initial
+                .min(self.settings.max)
+                .saturating_sub(current);
+        }
+
+        self.countdown = self.countdown.saturating_sub(1);
+        if self.countdown > 0 {
+            return 0;
+        }
+
+        self.countdown = self.settings.respawn_actions;
+        if current < self.settings.max {
+            1
+        } else {
+            0
+        }
+    }
+}
+
+/// Gets the manhattan distance between two cells
+pub fn distance(a: UVec2, b: UVec2) -> u32 {
+    let dx = a.x as i64 - b.x as i64;
+    let dy = a.y as i64 - b.y as i64;
+    (dx.abs() + dy.abs()) as u32
+}
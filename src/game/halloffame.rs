@@ -0,0 +1,67 @@
+//! AI hall of fame
+
+use serde::{Deserialize, Serialize};
+
+use super::dna::*;
+
+/// A hall of fame DNA
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HallOfFameEntry {
+    pub dna: Dna,
+
+    /// The generation the DNA was evaluated in
+    pub generation: usize,
+}
+
+/// The fittest DNA ever seen, fittest first
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HallOfFame {
+    capacity: usize,
+
+    entries: Vec<HallOfFameEntry>,
+}
+
+impl HallOfFame {
+    /// Creates a new, empty hall of fame
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            entries: Vec::with_capacity(capacity),
+        }
+    }
+
+    /// Gets the entries, fittest first
+    #[inline]
+    pub fn entries(&self) -> &[HallOfFameEntry] {
+        &self.entries
+    }
+
+    /// Records an evaluated generation
+    ///
+    /// DNA with the same genes as an entry only replaces it if it is fitter
+    pub fn record(&mut self, generation: usize, population: &[Dna]) {
+        for dna in population {
+            let fitness = dna.total_fitness();
+
+            if let Some(idx) = self
+                .entries
+                .iter()
+                .position(|entry| entry.dna.genes() == dna.genes())
+            {
+                if self.entries[idx].dna.total_fitness() >= fitness {
+                    continue;
+                }
+                self.entries.remove(idx);
+            }
+
+            self.entries.push(HallOfFameEntry {
+                dna: dna.clone(),
+                generation,
+            });
+        }
+
+        self.entries
+            .sort_by(|a, b| b.dna.total_fitness().total_cmp(&a.dna.total_fitness()));
+        self.entries.truncate(self.capacity);
+    }
+}
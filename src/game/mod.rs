@@ -1,4 +1,13 @@
 //! Game data
 
+pub mod balance;
+pub mod brain;
 pub mod dna;
+pub mod gridworld;
+pub mod halloffame;
+pub mod pareto;
+pub mod persist;
+pub mod replay;
+pub mod simulation;
+pub mod species;
 pub mod stats;
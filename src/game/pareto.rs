@@ -0,0 +1,133 @@
+//! NSGA-II style multi-objective ranking
+
+use super::stats::*;
+
+/// Pareto rank and crowding distance of each individual
+#[derive(Debug, Default, Clone)]
+pub struct ParetoRanking {
+    /// Index of the front each individual is in, 0 is the non-dominated front
+    pub ranks: Vec<usize>,
+
+    /// Crowding distance of each individual within its front
+    pub distances: Vec<f32>,
+
+    /// Individual indices in each front, best front first
+    pub fronts: Vec<Vec<usize>>,
+}
+
+impl ParetoRanking {
+    /// Ranks a set of fitnesses by non-dominated sorting
+    pub fn new(fitnesses: &[StatSetFitness]) -> Self {
+        let fronts = non_dominated_sort(fitnesses);
+
+        let mut ranks = vec![0; fitnesses.len()];
+        let mut distances = vec![0.0; fitnesses.len()];
+        for (rank, front) in fronts.iter().enumerate() {
+            for (idx, distance) in front.iter().zip(crowding_distance(fitnesses, front)) {
+                ranks[*idx] = rank;
+                distances[*idx] = distance;
+            }
+        }
+
+        Self {
+            ranks,
+            distances,
+            fronts,
+        }
+    }
+
+    /// Crowded comparison, true if a is better than b
+    ///
+    /// Lower ranks are better, ties go to the less crowded individual
+    pub fn better(&self, a: usize, b: usize) -> bool {
+        match self.ranks[a].cmp(&self.ranks[b]) {
+            std::cmp::Ordering::Less => true,
+            std::cmp::Ordering::Greater => false,
+            std::cmp::Ordering::Equal => self.distances[a] > self.distances[b],
+        }
+    }
+
+    /// Gets the individual indices, best first
+    pub fn sorted(&self) -> Vec<usize> {
+        let mut sorted = (0..self.ranks.len()).collect::<Vec<_>>();
+        sorted.sort_by(|a, b| {
+            self.ranks[*a]
+                .cmp(&self.ranks[*b])
+                .then(self.distances[*b].total_cmp(&self.distances[*a]))
+        });
+        sorted
+    }
+}
+
+/// Sorts individuals into fronts of non-dominated fitnesses
+fn non_dominated_sort(fitnesses: &[StatSetFitness]) -> Vec<Vec<usize>> {
+    let mut dominated = vec![vec![]; fitnesses.len()];
+    let mut domination_counts = vec![0; fitnesses.len()];
+
+    let mut front = vec![];
+    for (a, fitness) in fitnesses.iter().enumerate() {
+        for (b, other) in fitnesses.iter().enumerate() {
+            if fitness.dominates(other) {
+                dominated[a].push(b);
+            } else if other.dominates(fitness) {
+                domination_counts[a] += 1;
+            }
+        }
+
+        if domination_counts[a] == 0 {
+            front.push(a);
+        }
+    }
+
+    let mut fronts = vec![];
+    while !front.is_empty() {
+        let mut next = vec![];
+        for a in front.iter() {
+            for b in dominated[*a].iter() {
+                domination_counts[*b] -= 1;
+                if domination_counts[*b] == 0 {
+                    next.push(*b);
+                }
+            }
+        }
+
+        fronts.push(front);
+        front = next;
+    }
+
+    fronts
+}
+
+/// Gets the crowding distance of each individual in a front
+///
+/// Individuals at the edge of an objective have an infinite distance
+fn crowding_distance(fitnesses: &[StatSetFitness], front: &[usize]) -> Vec<f32> {
+    let mut distances = vec![0.0; front.len()];
+    if front.len() < 3 {
+        distances.fill(f32::INFINITY);
+        return distances;
+    }
+
+    for objective in 0..StatSetFitness::OBJECTIVES {
+        let value = |idx: usize| fitnesses[front[idx]].objectives()[objective];
+
+        let mut sorted = (0..front.len()).collect::<Vec<_>>();
+        sorted.sort_by(|a, b| value(*a).total_cmp(&value(*b)));
+
+        let first = sorted[0];
+        let last = sorted[sorted.len() - 1];
+        distances[first] = f32::INFINITY;
+        distances[last] = f32::INFINITY;
+
+        let range = value(last) - value(first);
+        if range <= 0.0 {
+            continue;
+        }
+
+        for window in sorted.windows(3) {
+            distances[window[1]] += (value(window[2]) - value(window[0])) / range;
+        }
+    }
+
+    distances
+}
@@ -0,0 +1,193 @@
+//! Versioned save data
+
+use std::error::Error;
+use std::fs;
+use std::path::Path;
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+/// Current save data schema version
+///
+/// Bump this when a saved type changes shape. New fields should be
+/// `#[serde(default)]` so older files still load,
+/// anything else is handled in [`Persist::migrate`]
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// Save data file format
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Format {
+    Ron,
+    Json,
+}
+
+impl Format {
+    /// Gets the format from a file extension, defaulting to RON
+    pub fn from_path(path: impl AsRef<Path>) -> Self {
+        match path.as_ref().extension().and_then(|ext| ext.to_str()) {
+            Some("json") => Self::Json,
+            _ => Self::Ron,
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct VersionedRef<'a, T> {
+    version: u32,
+    data: &'a T,
+}
+
+#[derive(Deserialize)]
+struct Versioned<T> {
+    version: u32,
+    data: T,
+}
+
+/// Versioned RON and JSON serialization
+pub trait Persist: Serialize + DeserializeOwned {
+    /// Whether to pretty print the data
+    const PRETTY: bool = true;
+
+    /// Upgrades data loaded from an older schema version
+    ///
+    /// Files written before versioning are version 0
+    fn migrate(self, _version: u32) -> Self {
+        self
+    }
+
+    /// Serializes the data with its schema version
+    fn to_string(&self, format: Format) -> Result<String, Box<dyn Error>> {
+        let versioned = VersionedRef {
+            version: SCHEMA_VERSION,
+            data: self,
+        };
+
+        Ok(match (format, Self::PRETTY) {
+            (Format::Ron, true) => {
+                ron::ser::to_string_pretty(&versioned, ron::ser::PrettyConfig::default())?
+            }
+            (Format::Ron, false) => ron::to_string(&versioned)?,
+            (Format::Json, true) => serde_json::to_string_pretty(&versioned)?,
+            (Format::Json, false) => serde_json::to_string(&versioned)?,
+        })
+    }
+
+    /// Deserializes versioned data, or unversioned data from before versioning
+    fn from_str(data: &str, format: Format) -> Result<Self, Box<dyn Error>> {
+        let versioned: Result<Versioned<Self>, Box<dyn Error>> = match format {
+            Format::Ron => ron::from_str(data).map_err(Into::into),
+            Format::Json => serde_json::from_str(data).map_err(Into::into),
+        };
+
+        let (version, data) = match versioned {
+            Ok(versioned) => (versioned.version, versioned.data),
+            Err(err) => {
+                let unversioned: Result<Self, Box<dyn Error>> = match format {
+                    Format::Ron => ron::from_str(data).map_err(Into::into),
+                    Format::Json => serde_json::from_str(data).map_err(Into::into),
+                };
+
+                // report the versioned error, that's what we expect to load
+                (0, unversioned.map_err(|_| err)?)
+            }
+        };
+
+        if version > SCHEMA_VERSION {
+            return Err(format!(
+                "Unsupported schema version {} (newest is {})",
+                version, SCHEMA_VERSION
+            )
+            .into());
+        }
+
+        Ok(data.migrate(version))
+    }
+
+    /// Loads the data from a file, the format is chosen by file extension
+    fn load(path: impl AsRef<Path>) -> Result<Self, Box<dyn Error>> {
+        let path = path.as_ref();
+        let data = fs::read_to_string(path)?;
+        Self::from_str(&data, Format::from_path(path))
+    }
+
+    /// Saves the data to a file, the format is chosen by file extension
+    fn save(&self, path: impl AsRef<Path>) -> Result<(), Box<dyn Error>> {
+        let path = path.as_ref();
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        fs::write(path, self.to_string(Format::from_path(path))?)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, Default, PartialEq, Serialize, Deserialize)]
+    struct TestData {
+        value: usize,
+        name: String,
+
+        /// The schema version the data was loaded from
+        #[serde(skip)]
+        version: u32,
+    }
+
+    impl Persist for TestData {
+        fn migrate(mut self, version: u32) -> Self {
+            self.version = version;
+            self
+        }
+    }
+
+    fn test_data() -> TestData {
+        TestData {
+            value: 42,
+            name: "test".to_owned(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn round_trip() {
+        for format in [Format::Ron, Format::Json] {
+            let data = test_data().to_string(format).unwrap();
+            let loaded = TestData::from_str(&data, format).unwrap();
+            assert_eq!(
+                loaded,
+                TestData {
+                    version: SCHEMA_VERSION,
+                    ..test_data()
+                }
+            );
+        }
+    }
+
+    #[test]
+    fn unversioned_is_version_0() {
+        let ron = TestData::from_str(r#"(value: 42, name: "test")"#, Format::Ron);
+        assert_eq!(ron.unwrap(), test_data());
+
+        let json = TestData::from_str(r#"{"value": 42, "name": "test"}"#, Format::Json);
+        assert_eq!(json.unwrap(), test_data());
+    }
+
+    #[test]
+    fn newer_version_is_rejected() {
+        let ron = format!(
+            r#"(version: {}, data: (value: 42, name: "test"))"#,
+            SCHEMA_VERSION + 1
+        );
+        assert!(TestData::from_str(&ron, Format::Ron).is_err());
+
+        let json = format!(
+            r#"{{"version": {}, "data": {{"value": 42, "name": "test"}}}}"#,
+            SCHEMA_VERSION + 1
+        );
+        assert!(TestData::from_str(&json, Format::Json).is_err());
+    }
+}
@@ -0,0 +1,249 @@
+//! Match replays
+
+use bevy::log::*;
+use bevy::math::UVec2;
+use serde::{Deserialize, Serialize};
+
+use crate::resources::*;
+
+use super::balance::*;
+use super::brain::*;
+use super::persist::*;
+use super::simulation::*;
+use super::stats::*;
+
+/// A single recorded turn
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReplayTurn {
+    /// The action taken
+    pub action: GameAction,
+
+    /// The acting automata cell after the action
+    pub cell: UVec2,
+
+    /// The damage dealt, if the action was an attack that hit
+    pub damage: Option<usize>,
+}
+
+/// The simulation state after a turn
+#[derive(Debug, Clone)]
+pub struct ReplayFrame {
+    pub player: Combatant,
+    pub ai: Combatant,
+    pub food: Vec<UVec2>,
+}
+
+impl ReplayFrame {
+    fn new(simulation: &Simulation) -> Self {
+        Self {
+            player: simulation.player().clone(),
+            ai: simulation.ai().clone(),
+            food: simulation.food().to_vec(),
+        }
+    }
+}
+
+/// A recorded match
+///
+/// Matches are deterministic, so the seed, stats and spawn cells
+/// are enough to play it back; the recorded turns verify the playback
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(try_from = "ReplayData")]
+pub struct Replay {
+    /// The round the match was played in
+    pub round: usize,
+
+    /// The simulation random seed
+    pub seed: u64,
+
+    pub width: usize,
+    pub height: usize,
+
+    pub player_stats: StatSet,
+    pub ai_stats: StatSet,
+
+    pub player_cell: UVec2,
+    pub ai_cell: UVec2,
+
+    /// The balance the match was played with
+    #[serde(default)]
+    pub balance: Balance,
+
+    /// The AI neural network controller, if it had one
+    #[serde(default)]
+    pub ai_brain: Option<Brain>,
+
+    pub turns: Vec<ReplayTurn>,
+}
+
+/// Serialized replay, checked before it becomes a Replay
+#[derive(Deserialize)]
+#[serde(rename = "Replay")]
+struct ReplayData {
+    round: usize,
+    seed: u64,
+    width: usize,
+    height: usize,
+    player_stats: StatSet,
+    ai_stats: StatSet,
+    player_cell: UVec2,
+    ai_cell: UVec2,
+    #[serde(default)]
+    balance: Balance,
+    #[serde(default)]
+    ai_brain: Option<Brain>,
+    turns: Vec<ReplayTurn>,
+}
+
+impl TryFrom<ReplayData> for Replay {
+    type Error = String;
+
+    fn try_from(replay: ReplayData) -> Result<Self, Self::Error> {
+        if replay.width == 0 || replay.height == 0 {
+            return Err(format!(
+                "Replay grid is {}x{}, expected at least 1x1",
+                replay.width, replay.height
+            ));
+        }
+
+        for cell in [replay.player_cell, replay.ai_cell] {
+            if cell.x as usize >= replay.width || cell.y as usize >= replay.height {
+                return Err(format!(
+                    "Replay spawn cell {} is outside the {}x{} grid",
+                    cell, replay.width, replay.height
+                ));
+            }
+        }
+
+        Ok(Self {
+            round: replay.round,
+            seed: replay.seed,
+            width: replay.width,
+            height: replay.height,
+            player_stats: replay.player_stats,
+            ai_stats: replay.ai_stats,
+            player_cell: replay.player_cell,
+            ai_cell: replay.ai_cell,
+            balance: replay.balance,
+            ai_brain: replay.ai_brain,
+            turns: replay.turns,
+        })
+    }
+}
+
+impl Persist for Replay {
+    // replays can run for many turns
+    const PRETTY: bool = false;
+}
+
+impl Replay {
+    /// Creates the simulation the replay was recorded from
+    pub fn simulation(&self) -> Simulation {
+        Simulation::with_player_cell(
+            self.player_stats,
+            self.ai_stats,
+            self.width,
+            self.height,
+            self.player_cell,
+            self.balance,
+            Random::new(self.seed),
+        )
+        .with_ai_brain(self.ai_brain.clone())
+    }
+
+    /// Plays back the replay
+    ///
+    /// Returns the initial state followed by the state after each turn
+    pub fn frames(&self) -> Vec<ReplayFrame> {
+        let mut simulation = self.simulation();
+        if simulation.ai().cell != self.ai_cell {
+            warn!(
+                "Replay AI spawn mismatch: {} != {}",
+                simulation.ai().cell,
+                self.ai_cell
+            );
+        }
+
+        let mut frames = Vec::with_capacity(self.turns.len() + 1);
+        frames.push(ReplayFrame::new(&simulation));
+
+        for (idx, turn) in self.turns.iter().enumerate() {
+            simulation.step();
+
+            let recorded = simulation.replay().turns.get(idx);
+            if recorded != Some(turn) {
+                warn!(
+                    "Replay desync on turn {}: {:?} != {:?}",
+                    idx, recorded, turn
+                );
+            }
+
+            frames.push(ReplayFrame::new(&simulation));
+        }
+
+        frames
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn replay_keeps_balance() {
+        let balance = Balance {
+            base_health: 20,
+            strength_mod: 1.0,
+            ..Balance::DEFAULT
+        };
+
+        let mut random = Random::new(0);
+        let mut simulation = Simulation::new(
+            StatSet::random(balance.stat_points, &mut random),
+            StatSet::random(balance.stat_points, &mut random),
+            10,
+            10,
+            balance,
+            Random::new(1),
+        );
+        while !simulation.is_finished() {
+            simulation.step();
+        }
+
+        let replay = Replay::from_str(
+            &simulation.replay().to_string(Format::Ron).unwrap(),
+            Format::Ron,
+        )
+        .unwrap();
+        assert_eq!(replay.balance, balance);
+
+        let mut playback = replay.simulation();
+        while !playback.is_finished() {
+            playback.step();
+        }
+        assert_eq!(playback.replay().turns, replay.turns);
+    }
+
+    #[test]
+    fn grid_is_validated() {
+        let mut random = Random::new(0);
+        let simulation = Simulation::new(
+            StatSet::random(Balance::DEFAULT.stat_points, &mut random),
+            StatSet::random(Balance::DEFAULT.stat_points, &mut random),
+            10,
+            10,
+            Balance::DEFAULT,
+            Random::new(1),
+        );
+
+        let mut replay = simulation.replay().clone();
+        replay.width = 0;
+        let data = replay.to_string(Format::Ron).unwrap();
+        assert!(Replay::from_str(&data, Format::Ron).is_err());
+
+        let mut replay = simulation.replay().clone();
+        replay.player_cell = UVec2::new(10, 0);
+        let data = replay.to_string(Format::Ron).unwrap();
+        assert!(Replay::from_str(&data, Format::Ron).is_err());
+    }
+}
@@ -0,0 +1,700 @@
+//! Headless match simulation
+//!
+//! Runs a full match between two automata without any ECS,
+//! the Game state systems only render the simulation state
+
+use std::collections::HashSet;
+
+use bevy::math::UVec2;
+use derivative::*;
+use serde::{Deserialize, Serialize};
+
+use crate::resources::*;
+
+use super::balance::*;
+use super::brain::*;
+use super::gridworld::*;
+use super::replay::*;
+use super::stats::*;
+
+/// Maximum number of turns before a match is called
+pub const MAX_TURNS: usize = 1000;
+
+/// The simulation turn actions, in turn order
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Derivative, Serialize, Deserialize)]
+#[derivative(Default)]
+pub enum GameAction {
+    /// The player's move action
+    #[derivative(Default)]
+    PlayerMove,
+
+    /// The player's attack action
+    PlayerAttack,
+
+    /// The AI's move action
+    AIMove,
+
+    /// The AI's attack action
+    AIAttack,
+}
+
+/// The winner of a round
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize, Deserialize)]
+pub enum RoundWinner {
+    /// The player's automata won
+    Player,
+
+    /// The AI's automata won
+    AI,
+}
+
+/// How an automata chose to move
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MoveKind {
+    /// Moved towards the enemy automata
+    Enemy,
+
+    /// Moved towards the nearest food
+    Food,
+
+    /// Random walk
+    Random,
+
+    /// Chosen by a neural network controller
+    Brain {
+        /// The chosen direction output activation
+        output: f32,
+    },
+}
+
+/// A single movement step decision
+struct MoveStep {
+    cell: UVec2,
+    kind: MoveKind,
+    enemy_roll: Option<f64>,
+    food_roll: Option<f64>,
+}
+
+/// Something that happened during a simulation turn
+#[derive(Debug, Clone, PartialEq)]
+pub enum SimulationEvent {
+    /// An automata moved a single cell
+    Moved {
+        player: bool,
+        from: UVec2,
+        to: UVec2,
+        kind: MoveKind,
+
+        /// The move towards enemy roll, if it was rolled
+        enemy_roll: Option<f64>,
+
+        /// The move towards food roll, if it was rolled
+        food_roll: Option<f64>,
+    },
+
+    /// An automata hit the enemy automata
+    Attacked {
+        player: bool,
+        damage: usize,
+        absorbed: usize,
+    },
+
+    /// An automata ate food
+    AteFood {
+        player: bool,
+        cell: UVec2,
+        health: usize,
+    },
+
+    /// Food spawned
+    FoodSpawned { cell: UVec2 },
+
+    /// An automata was defeated
+    Died { player: bool },
+}
+
+/// Per-match automata metrics
+#[derive(Debug, Clone, Default)]
+pub struct MatchMetrics {
+    /// Number of cells in the GridWorld
+    pub grid_cells: usize,
+
+    /// The automata initial health
+    pub initial_health: usize,
+
+    /// The enemy automata initial health
+    pub enemy_health: usize,
+
+    /// Number of cells moved
+    pub moves: usize,
+
+    /// Number of moves that closed the distance to the enemy
+    pub closing_moves: usize,
+
+    /// Distinct cells visited, including the spawn cell
+    pub visited: HashSet<UVec2>,
+
+    pub damage_dealt: usize,
+    pub damage_taken: usize,
+    pub damage_absorbed: usize,
+
+    pub food_eaten: usize,
+}
+
+impl MatchMetrics {
+    fn new(grid_cells: usize, initial_health: usize, enemy_health: usize, cell: UVec2) -> Self {
+        let mut visited = HashSet::new();
+        visited.insert(cell);
+
+        Self {
+            grid_cells,
+            initial_health,
+            enemy_health,
+            visited,
+            ..Default::default()
+        }
+    }
+
+    /// Gets the number of distinct cells visited
+    #[inline]
+    pub fn cells_covered(&self) -> usize {
+        self.visited.len()
+    }
+}
+
+/// The result of a completed match
+#[derive(Debug, Clone)]
+pub struct SimulationResult {
+    /// The winner, if there was one
+    pub winner: Option<RoundWinner>,
+
+    /// The number of turns taken
+    pub turns: usize,
+
+    /// The final player automata health
+    pub player_health: usize,
+
+    /// The final AI automata health
+    pub ai_health: usize,
+
+    pub player_metrics: MatchMetrics,
+    pub ai_metrics: MatchMetrics,
+}
+
+/// A single automata taking part in a match
+#[derive(Debug, Clone)]
+pub struct Combatant {
+    pub stats: StatSet,
+
+    /// Current GridWorld cell
+    pub cell: UVec2,
+
+    /// Current HP (health)
+    pub health: usize,
+
+    /// Initial HP, which food can't heal past
+    pub initial_health: usize,
+
+    pub metrics: MatchMetrics,
+
+    /// Neural network controller, if the automata isn't stat driven
+    pub brain: Option<Brain>,
+}
+
+impl Combatant {
+    fn new(
+        stats: StatSet,
+        cell: UVec2,
+        enemy: &StatSet,
+        grid_cells: usize,
+        balance: &Balance,
+    ) -> Self {
+        let initial_health = stats.initial_health(balance);
+
+        Self {
+            stats,
+            cell,
+            health: initial_health,
+            initial_health,
+            metrics: MatchMetrics::new(
+                grid_cells,
+                initial_health,
+                enemy.initial_health(balance),
+                cell,
+            ),
+            brain: None,
+        }
+    }
+}
+
+/// A headless match between the player and an AI automata
+pub struct Simulation {
+    gridworld: GridWorld,
+
+    player: Combatant,
+    ai: Combatant,
+
+    food: Vec<UVec2>,
+    food_spawner: FoodSpawner,
+
+    /// The balance the match is played with,
+    /// so balance changes don't affect a running match
+    balance: Balance,
+
+    action: GameAction,
+    turn: usize,
+
+    random: Random,
+
+    replay: Replay,
+}
+
+impl Simulation {
+    /// Creates a new match with a random player spawn cell
+    pub fn new(
+        player: StatSet,
+        ai: StatSet,
+        width: usize,
+        height: usize,
+        balance: Balance,
+        mut random: Random,
+    ) -> Self {
+        let player_cell = UVec2::new(
+            random.random_range(0..width as u32),
+            random.random_range(0..height as u32),
+        );
+
+        Self::with_player_cell(player, ai, width, height, player_cell, balance, random)
+    }
+
+    /// Creates a new match with the player spawning in the given cell
+    ///
+    /// The AI spawns in the mirror cell
+    pub fn with_player_cell(
+        player: StatSet,
+        ai: StatSet,
+        width: usize,
+        height: usize,
+        player_cell: UVec2,
+        balance: Balance,
+        mut random: Random,
+    ) -> Self {
+        let ai_cell = mirror_cell(player_cell, width, height, &mut random);
+
+        let replay = Replay {
+            round: 0,
+            seed: random.seed(),
+            width,
+            height,
+            player_stats: player,
+            ai_stats: ai,
+            player_cell,
+            ai_cell,
+            balance,
+            ai_brain: None,
+            turns: vec![],
+        };
+
+        let mut simulation = Self {
+            gridworld: GridWorld::new(width, height),
+            player: Combatant::new(player, player_cell, &ai, width * height, &balance),
+            ai: Combatant::new(ai, ai_cell, &player, width * height, &balance),
+            food: vec![],
+            food_spawner: FoodSpawner::new(balance.food),
+            balance,
+            action: GameAction::default(),
+            turn: 0,
+            random,
+            replay,
+        };
+
+        // initial food
+        let mut events = vec![];
+        simulation.spawn_food(&mut events);
+
+        simulation
+    }
+
+    /// Sets the AI automata neural network controller
+    ///
+    /// This must be done before the first turn
+    pub fn with_ai_brain(mut self, brain: Option<Brain>) -> Self {
+        self.ai.brain = brain.clone();
+        self.replay.ai_brain = brain;
+        self
+    }
+
+    /// Gets the player automata
+    #[inline]
+    pub fn player(&self) -> &Combatant {
+        &self.player
+    }
+
+    /// Gets the AI automata
+    #[inline]
+    pub fn ai(&self) -> &Combatant {
+        &self.ai
+    }
+
+    /// Gets the food cells
+    #[inline]
+    pub fn food(&self) -> &[UVec2] {
+        &self.food
+    }
+
+    /// Gets the number of turns taken
+    #[inline]
+    pub fn turn(&self) -> usize {
+        self.turn
+    }
+
+    /// Gets the next action to be taken
+    #[inline]
+    pub fn action(&self) -> GameAction {
+        self.action
+    }
+
+    /// Gets the match replay recorded so far
+    #[inline]
+    pub fn replay(&self) -> &Replay {
+        &self.replay
+    }
+
+    /// Checks if the match is over
+    pub fn is_finished(&self) -> bool {
+        self.player.health == 0 || self.ai.health == 0 || self.turn >= MAX_TURNS
+    }
+
+    /// Gets the match winner
+    ///
+    /// Matches that run out of turns go to the healthiest automata
+    pub fn winner(&self) -> Option<RoundWinner> {
+        if !self.is_finished() {
+            return None;
+        }
+
+        match self.player.health.cmp(&self.ai.health) {
+            std::cmp::Ordering::Greater => Some(RoundWinner::Player),
+            std::cmp::Ordering::Less => Some(RoundWinner::AI),
+            std::cmp::Ordering::Equal => None,
+        }
+    }
+
+    /// Gets the current match result
+    pub fn result(&self) -> SimulationResult {
+        SimulationResult {
+            winner: self.winner(),
+            turns: self.turn,
+            player_health: self.player.health,
+            ai_health: self.ai.health,
+            player_metrics: self.player.metrics.clone(),
+            ai_metrics: self.ai.metrics.clone(),
+        }
+    }
+
+    /// Runs the match to completion
+    pub fn run(mut self) -> SimulationResult {
+        while !self.is_finished() {
+            self.step();
+        }
+
+        self.result()
+    }
+
+    /// Runs a single turn
+    ///
+    /// Returns what happened during the turn
+    pub fn step(&mut self) -> Vec<SimulationEvent> {
+        let mut events = vec![];
+        if self.is_finished() {
+            return events;
+        }
+
+        let action = self.action;
+        match action {
+            GameAction::PlayerMove => {
+                self.move_action(true, &mut events);
+                self.action = GameAction::PlayerAttack;
+            }
+            GameAction::PlayerAttack => {
+                self.attack_action(true, &mut events);
+                self.action = GameAction::AIMove;
+            }
+            GameAction::AIMove => {
+                self.move_action(false, &mut events);
+                self.action = GameAction::AIAttack;
+            }
+            GameAction::AIAttack => {
+                self.attack_action(false, &mut events);
+                self.action = GameAction::PlayerMove;
+            }
+        }
+
+        self.record(action, &events);
+
+        self.spawn_food(&mut events);
+
+        self.turn += 1;
+
+        events
+    }
+
+    /// Records a turn in the replay
+    fn record(&mut self, action: GameAction, events: &[SimulationEvent]) {
+        let player = matches!(action, GameAction::PlayerMove | GameAction::PlayerAttack);
+        let cell = if player {
+            self.player.cell
+        } else {
+            self.ai.cell
+        };
+
+        let damage = events.iter().find_map(|event| match event {
+            SimulationEvent::Attacked { damage, .. } => Some(*damage),
+            _ => None,
+        });
+
+        self.replay.turns.push(ReplayTurn {
+            action,
+            cell,
+            damage,
+        });
+    }
+
+    fn combatants(&mut self, player: bool) -> (&mut Combatant, &mut Combatant) {
+        if player {
+            (&mut self.player, &mut self.ai)
+        } else {
+            (&mut self.ai, &mut self.player)
+        }
+    }
+
+    /// Picks the cell for a single movement step
+    fn step_cell(&mut self, player: bool) -> MoveStep {
+        let (combatant, enemy) = if player {
+            (&self.player, &self.ai)
+        } else {
+            (&self.ai, &self.player)
+        };
+
+        if let Some(brain) = &combatant.brain {
+            let decision = brain.decide(&self.brain_inputs(combatant, enemy));
+            let cell = decision
+                .direction
+                .step(
+                    combatant.cell,
+                    self.gridworld.width(),
+                    self.gridworld.height(),
+                )
+                .unwrap_or(combatant.cell);
+            return MoveStep {
+                cell,
+                kind: MoveKind::Brain {
+                    output: decision.confidence,
+                },
+                enemy_roll: None,
+                food_roll: None,
+            };
+        }
+
+        let (towards_enemy, enemy_roll) = combatant
+            .stats
+            .move_towards_enemy(&self.balance, &mut self.random);
+        if towards_enemy {
+            let cell = self
+                .gridworld
+                .step_towards(combatant.cell, enemy.cell, &mut self.random);
+            return MoveStep {
+                cell,
+                kind: MoveKind::Enemy,
+                enemy_roll: Some(enemy_roll),
+                food_roll: None,
+            };
+        }
+
+        let (towards_food, food_roll) = combatant
+            .stats
+            .move_towards_food(&self.balance, &mut self.random);
+        if towards_food {
+            if let Some(target) = self
+                .food
+                .iter()
+                .min_by_key(|food| distance(combatant.cell, **food))
+            {
+                let cell = self
+                    .gridworld
+                    .step_towards(combatant.cell, *target, &mut self.random);
+                return MoveStep {
+                    cell,
+                    kind: MoveKind::Food,
+                    enemy_roll: Some(enemy_roll),
+                    food_roll: Some(food_roll),
+                };
+            }
+        }
+
+        let cell = self
+            .gridworld
+            .random_neighbor(combatant.cell, &mut self.random);
+        MoveStep {
+            cell,
+            kind: MoveKind::Random,
+            enemy_roll: Some(enemy_roll),
+            food_roll: Some(food_roll),
+        }
+    }
+
+    /// Gets what a neural network controller can see
+    fn brain_inputs(&self, combatant: &Combatant, enemy: &Combatant) -> BrainInputs {
+        let width = self.gridworld.width() as f32;
+        let height = self.gridworld.height() as f32;
+        let offset = |target: UVec2| {
+            (
+                (target.x as f32 - combatant.cell.x as f32) / width,
+                (target.y as f32 - combatant.cell.y as f32) / height,
+            )
+        };
+
+        let food = self
+            .food
+            .iter()
+            .min_by_key(|food| distance(combatant.cell, **food))
+            .map(|food| offset(*food))
+            .unwrap_or_default();
+
+        BrainInputs {
+            enemy: offset(enemy.cell),
+            food,
+            health: combatant.health as f32 / combatant.initial_health.max(1) as f32,
+            enemy_health: enemy.health as f32 / enemy.initial_health.max(1) as f32,
+        }
+    }
+
+    /// Moves up to the stat set movement number of cells,
+    /// eating any food moved onto
+    fn move_action(&mut self, player: bool, events: &mut Vec<SimulationEvent>) {
+        let balance = self.balance;
+        let movement = self.combatants(player).0.stats.movement(&balance);
+        for _ in 0..movement {
+            let step = self.step_cell(player);
+
+            let food_health = self.food_spawner.health();
+            let (combatant, enemy) = self.combatants(player);
+
+            // can't move into the enemy cell, or stay put
+            if step.cell == enemy.cell || step.cell == combatant.cell {
+                continue;
+            }
+
+            events.push(SimulationEvent::Moved {
+                player,
+                from: combatant.cell,
+                to: step.cell,
+                kind: step.kind,
+                enemy_roll: step.enemy_roll,
+                food_roll: step.food_roll,
+            });
+
+            combatant.metrics.moves += 1;
+            if distance(step.cell, enemy.cell) < distance(combatant.cell, enemy.cell) {
+                combatant.metrics.closing_moves += 1;
+            }
+            combatant.metrics.visited.insert(step.cell);
+
+            combatant.cell = step.cell;
+
+            if let Some(idx) = self.food.iter().position(|food| *food == step.cell) {
+                self.food.swap_remove(idx);
+
+                let (combatant, _) = self.combatants(player);
+                combatant.health = (combatant.health + food_health).min(combatant.initial_health);
+                combatant.metrics.food_eaten += 1;
+
+                events.push(SimulationEvent::AteFood {
+                    player,
+                    cell: step.cell,
+                    health: combatant.health,
+                });
+            }
+        }
+    }
+
+    /// Attacks the enemy automata if it is in an adjacent cell
+    ///
+    /// Neural network controllers may also choose not to attack
+    fn attack_action(&mut self, player: bool, events: &mut Vec<SimulationEvent>) {
+        let (combatant, enemy) = if player {
+            (&self.player, &self.ai)
+        } else {
+            (&self.ai, &self.player)
+        };
+        if distance(combatant.cell, enemy.cell) != 1 {
+            return;
+        }
+
+        if let Some(brain) = &combatant.brain {
+            if !brain.decide(&self.brain_inputs(combatant, enemy)).attack {
+                return;
+            }
+        }
+
+        let balance = self.balance;
+        let (combatant, enemy) = self.combatants(player);
+
+        let attack = combatant.stats.attack_damage(&balance);
+        let absorbed = enemy.stats.absorbed_damage(&balance).min(attack);
+        let damage = attack - absorbed;
+        enemy.health = enemy.health.saturating_sub(damage);
+
+        combatant.metrics.damage_dealt += damage;
+        enemy.metrics.damage_taken += damage;
+        enemy.metrics.damage_absorbed += absorbed;
+
+        events.push(SimulationEvent::Attacked {
+            player,
+            damage,
+            absorbed,
+        });
+
+        if enemy.health == 0 {
+            events.push(SimulationEvent::Died { player: !player });
+        }
+    }
+
+    /// Spawns any food the food spawner is ready for
+    fn spawn_food(&mut self, events: &mut Vec<SimulationEvent>) {
+        for _ in 0..self.food_spawner.tick(self.food.len()) {
+            let mut occupied = self.food.clone();
+            occupied.push(self.player.cell);
+            occupied.push(self.ai.cell);
+
+            if let Some(cell) = self.gridworld.random_free_cell(&occupied, &mut self.random) {
+                self.food.push(cell);
+                events.push(SimulationEvent::FoodSpawned { cell });
+            }
+        }
+    }
+}
+
+/// Gets the mirror of a cell across the center of the grid
+///
+/// Cells on the center line mirror to a random edge
+fn mirror_cell(cell: UVec2, width: usize, height: usize, random: &mut Random) -> UVec2 {
+    UVec2::new(
+        mirror_coordinate(cell.x, width, random),
+        mirror_coordinate(cell.y, height, random),
+    )
+}
+
+/// Mirrors a single cell coordinate
+fn mirror_coordinate(v: u32, size: usize, random: &mut Random) -> u32 {
+    let max = size as u32 - 1;
+    if v * 2 != max {
+        return max - v;
+    }
+
+    if random.coin() {
+        0
+    } else {
+        max
+    }
+}
@@ -0,0 +1,85 @@
+//! DNA speciation
+
+use serde::{Deserialize, Serialize};
+
+use super::dna::*;
+use super::stats::*;
+
+/// A group of similar DNA
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Species {
+    /// The stats that define the species
+    pub representative: StatSet,
+
+    /// The fittest member
+    pub champion: Dna,
+
+    /// The total fitness of the fittest member
+    pub best_fitness: f32,
+
+    /// Number of members
+    pub size: usize,
+}
+
+impl Species {
+    fn new(dna: &Dna) -> Self {
+        Self {
+            representative: *dna.genes(),
+            champion: dna.clone(),
+            best_fitness: dna.total_fitness(),
+            size: 1,
+        }
+    }
+
+    fn add(&mut self, dna: &Dna) {
+        if dna.total_fitness() > self.best_fitness {
+            self.champion = dna.clone();
+            self.best_fitness = dna.total_fitness();
+        }
+        self.size += 1;
+    }
+}
+
+/// Groups DNA into species
+///
+/// DNA joins the first species whose representative is within
+/// the distance threshold, otherwise it starts a new species.
+/// Returns the species, fittest first, and the species index of each DNA
+pub fn speciate(population: &[Dna], threshold: isize) -> (Vec<Species>, Vec<usize>) {
+    let mut species: Vec<Species> = vec![];
+    let mut membership = Vec::with_capacity(population.len());
+
+    for dna in population {
+        match species
+            .iter()
+            .position(|species| species.representative.distance(dna.genes()) <= threshold)
+        {
+            Some(idx) => {
+                species[idx].add(dna);
+                membership.push(idx);
+            }
+            None => {
+                membership.push(species.len());
+                species.push(Species::new(dna));
+            }
+        }
+    }
+
+    // sort fittest first, keeping the membership indices in sync
+    let mut order = (0..species.len()).collect::<Vec<_>>();
+    order.sort_by(|a, b| {
+        species[*b]
+            .best_fitness
+            .total_cmp(&species[*a].best_fitness)
+    });
+
+    let mut remap = vec![0; species.len()];
+    for (new, old) in order.iter().enumerate() {
+        remap[*old] = new;
+    }
+
+    let species = order.into_iter().map(|idx| species[idx].clone()).collect();
+    let membership = membership.into_iter().map(|idx| remap[idx]).collect();
+
+    (species, membership)
+}
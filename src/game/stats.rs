@@ -1,164 +1,268 @@
 //! Automata stats
 
-use std::borrow::Cow;
+use std::cmp::Reverse;
 
 use bevy_inspector_egui::prelude::*;
-use paste::paste;
+use serde::{Deserialize, Serialize};
 
 use crate::resources::*;
 
-/// Base automata health
-const BASE_HEALTH: isize = 10;
+use super::balance::*;
+use super::persist::*;
+use super::simulation::*;
 
-/// Constitution modifier for calculating initial health
-const CONSTITUTION_MOD: f32 = 1.0;
+/// A stat declaration in the stat registry
+#[derive(Clone, Copy)]
+pub struct StatDefinition {
+    pub id: StatId,
 
-/// Base automata movement
-const BASE_MOVEMENT: isize = 1;
+    pub name: &'static str,
 
-/// Dexterity modifier for calculating movement
-const DEXTERITY_MOD: f32 = 0.2;
+    /// What the stat does, shown in the UI
+    pub description: &'static str,
 
-/// Base automata attack damage
-const BASE_ATTACK: isize = 1;
+    /// Calculates the value derived from the stat with the given balance
+    pub derived: fn(isize, &Balance) -> f64,
 
-/// Strength modifier for calculating attack damage
-const STRENGTH_MOD: f32 = 0.25;
+    /// Measures how well a match played to the stat, in the range [0..1],
+    /// from the final health and the match metrics
+    pub fitness: fn(usize, &MatchMetrics) -> f32,
 
-/// Base automata attack damage absorb
-const BASE_ATTACK_ABSORB: isize = 0;
+    /// Weight of the fitness term in the total fitness
+    pub fitness_weight: f32,
+}
 
-/// Fortitude modifier for calculating damage absorb
-const FORTITUDE_MOD: f32 = 0.25;
+/// A single automata stat
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Inspectable, Default, Serialize, Deserialize)]
+pub struct Stat {
+    value: isize,
+}
 
-/// Base chance to move towards the enemy automata
-const BASE_MOVE_TOWARDS_ENEMY: f64 = 0.1; // 10% starting chance
+impl From<isize> for Stat {
+    #[inline]
+    fn from(value: isize) -> Self {
+        Self { value }
+    }
+}
 
-/// Aggression modifier for calculating chance to move towards enemy automata
-const AGGRESSION_MOD: f64 = 0.05 / 5.0; // 5% chance every 5 points
+/// Declares the stat registry
+///
+/// Generates StatId, STATS and the StatSet and StatSetFitness fields,
+/// stats are listed in UI order
+macro_rules! stats {
+    ($(
+        $(#[$meta:meta])*
+        $id:ident($key:ident) {
+            name: $name:literal,
+            description: $description:literal,
+            derived: $derived:expr,
+            fitness: $fitness:expr,
+            fitness_weight: $fitness_weight:expr,
+        }
+    )*) => {
+        /// Stat identifier enum for things that need it
+        #[derive(Debug, Eq, PartialEq, Copy, Clone, Inspectable, Serialize, Deserialize)]
+        pub enum StatId {
+            $(
+                $(#[$meta])*
+                $id,
+            )*
+        }
 
-/// Base chance to move towards food
-const BASE_MOVE_TOWARDS_FOOD: f64 = 0.1;
+        impl StatId {
+            /// All of the stats, in UI order
+            pub const ALL: [StatId; STATS.len()] = [$(StatId::$id,)*];
+        }
 
-/// Intellect modifier for calculating chance to move towards food
-const INTELLECT_MOD: f64 = 0.05 / 5.0; // 5% chance every 5 points
+        /// All of the stat definitions, in UI order
+        pub const STATS: &[StatDefinition] = &[$(
+            StatDefinition {
+                id: StatId::$id,
+                name: $name,
+                description: $description,
+                derived: $derived,
+                fitness: $fitness,
+                fitness_weight: $fitness_weight,
+            },
+        )*];
+
+        /// A set of automata stats
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Inspectable, Default, Serialize, Deserialize)]
+        pub struct StatSet {
+            $($key: Stat,)*
+        }
 
-/// Stat identifier enum for things that need it
-#[derive(Debug, Eq, PartialEq, Copy, Clone, Inspectable)]
-pub enum StatId {
+        impl StatSet {
+            /// Creates a new stat set
+            #[allow(dead_code)]
+            pub fn new($($key: Stat),*) -> Self {
+                Self { $($key),* }
+            }
+
+            /// Returns the value of the given stat
+            pub fn value(&self, statid: StatId) -> isize {
+                match statid {
+                    $(StatId::$id => self.$key.value,)*
+                }
+            }
+
+            /// Modifies a stat by amount
+            #[inline]
+            fn modify(&mut self, statid: StatId, amount: isize) {
+                match statid {
+                    $(StatId::$id => self.$key.value += amount,)*
+                }
+            }
+        }
+
+        /// Per-stat genetic fitness, each term is in the range [0..1]
+        #[derive(Debug, Clone, Copy, Inspectable, Default, Serialize, Deserialize)]
+        pub struct StatSetFitness {
+            $($key: f32,)*
+        }
+
+        impl StatSetFitness {
+            /// Gets the fitness term of the given stat
+            pub fn value(&self, statid: StatId) -> f32 {
+                match statid {
+                    $(StatId::$id => self.$key,)*
+                }
+            }
+
+            /// Sets the fitness term of the given stat
+            pub fn set_value(&mut self, statid: StatId, value: f32) {
+                match statid {
+                    $(StatId::$id => self.$key = value,)*
+                }
+            }
+        }
+    };
+}
+
+stats! {
     /// Constitution - HP
-    Constitution,
+    Constitution(constitution) {
+        name: "Constitution",
+        description: "Initial health",
+        derived: |value, balance| {
+            (balance.base_health + (value as f32 * balance.constitution_mod) as isize).max(1) as f64
+        },
+        // survive with as much health as possible
+        fitness: |health, metrics| {
+            health.pow(2) as f32 / metrics.initial_health.max(1).pow(2) as f32
+        },
+        fitness_weight: 1.0,
+    }
 
     /// Dexterity - Movement
-    Dexterity,
+    Dexterity(dexterity) {
+        name: "Dexterity",
+        description: "Movement",
+        derived: |value, balance| {
+            (balance.base_movement + (value as f32 * balance.dexterity_mod) as isize).max(1) as f64
+        },
+        // cover as much of the grid as possible
+        fitness: |_, metrics| ratio(metrics.cells_covered(), metrics.grid_cells),
+        fitness_weight: 0.25,
+    }
 
     /// Strength - Attack
-    Strength,
+    Strength(strength) {
+        name: "Strength",
+        description: "Attack damage",
+        derived: |value, balance| {
+            (balance.base_attack + (value as f32 * balance.strength_mod) as isize).max(1) as f64
+        },
+        // deal as much damage as possible
+        fitness: |_, metrics| ratio(metrics.damage_dealt, metrics.enemy_health),
+        fitness_weight: 1.0,
+    }
 
     /// Fortitude - Defense
-    Fortitude,
+    Fortitude(fortitude) {
+        name: "Fortitude",
+        description: "Damage absorb",
+        derived: |value, balance| {
+            (balance.base_attack_absorb + (value as f32 * balance.fortitude_mod) as isize).max(1)
+                as f64
+        },
+        // absorb as much of the incoming damage as possible
+        fitness: |_, metrics| {
+            ratio(
+                metrics.damage_absorbed,
+                metrics.damage_absorbed + metrics.damage_taken,
+            )
+        },
+        fitness_weight: 0.5,
+    }
 
     /// Aggression - Move towards enemy automata
-    Aggression,
+    Aggression(aggression) {
+        name: "Aggression",
+        description: "Chance to move towards enemy",
+        derived: |value, balance| {
+            (balance.base_move_towards_enemy + value as f64 * balance.aggression_mod)
+                .clamp(0.0, balance.max_move_chance)
+        },
+        // spend as many moves as possible closing in on the enemy
+        fitness: |_, metrics| ratio(metrics.closing_moves, metrics.moves),
+        fitness_weight: 0.5,
+    }
 
     /// Intellect - Move towards food
-    Intellect,
+    Intellect(intellect) {
+        name: "Intellect",
+        description: "Chance to move towards food",
+        derived: |value, balance| {
+            (balance.base_move_towards_food + value as f64 * balance.intellect_mod)
+                .clamp(0.0, balance.max_move_chance)
+        },
+        // eat as much food as possible, with diminishing returns
+        fitness: |_, metrics| 1.0 - 1.0 / (metrics.food_eaten + 1) as f32,
+        fitness_weight: 0.25,
+    }
 }
 
-impl StatId {
-    // TODO: replace this with a From<> impl
-    pub fn name(&self) -> Cow<'static, str> {
-        match self {
-            StatId::Constitution => "Constitution".into(),
-            StatId::Dexterity => "Dexterity".into(),
-            StatId::Strength => "Strength".into(),
-            StatId::Fortitude => "Fortitude".into(),
-            StatId::Aggression => "Aggression".into(),
-            StatId::Intellect => "Intellect".into(),
-        }
+/// Gets the ratio of two values, 0 if there is nothing to compare against
+fn ratio(value: usize, total: usize) -> f32 {
+    if total == 0 {
+        return 0.0;
     }
-}
 
-/// A single automata stat
-#[derive(Debug, Clone, Copy, Inspectable, Default)]
-pub struct Stat {
-    value: isize,
+    (value as f32 / total as f32).min(1.0)
 }
 
-impl From<isize> for Stat {
+impl StatId {
+    /// Gets the stat definition from the registry
     #[inline]
-    fn from(value: isize) -> Self {
-        Self { value }
+    pub fn definition(&self) -> &'static StatDefinition {
+        &STATS[*self as usize]
     }
-}
-
-/// A set of automata stats
-#[derive(Debug, Clone, Copy, Inspectable, Default)]
-pub struct StatSet {
-    constitution: Stat,
-    dexterity: Stat,
-    strength: Stat,
-    fortitude: Stat,
-    aggression: Stat,
-    intellect: Stat,
-}
 
-macro_rules! impl_stat {
-    ($statid:tt) => {
-        /// Gets the value of the stat
-        #[inline]
-        pub fn $statid(&self) -> isize {
-            self.$statid.value
-        }
+    #[inline]
+    pub fn name(&self) -> &'static str {
+        self.definition().name
+    }
 
-        paste! {
-            /// Sets the value of the stat
-            #[inline]
-            pub fn [<set_ $statid>](&mut self, value: isize) {
-                self.$statid.value = value;
-            }
-        }
-    };
+    #[inline]
+    pub fn description(&self) -> &'static str {
+        self.definition().description
+    }
 }
 
+impl Persist for StatSet {}
+
 impl StatSet {
     pub fn size() -> usize {
         1
     }
 
-    /// Creates a new stat set
-    #[allow(dead_code)]
-    pub fn new(
-        constitution: Stat,
-        dexterity: Stat,
-        strength: Stat,
-        fortitude: Stat,
-        aggression: Stat,
-        intellect: Stat,
-    ) -> Self {
-        Self {
-            constitution,
-            dexterity,
-            strength,
-            fortitude,
-            aggression,
-            intellect,
-        }
-    }
-
     /// Crates a new, randomized stat set
     pub fn random(mut points: isize, random: &mut Random) -> Self {
         let mut stats = Self::default();
 
         // shuffle the stat types
-        let mut buckets = vec![
-            StatId::Constitution,
-            StatId::Dexterity,
-            StatId::Strength,
-            StatId::Fortitude,
-            StatId::Aggression,
-            StatId::Intellect,
-        ];
+        let mut buckets = StatId::ALL.to_vec();
         random.shuffle(&mut buckets);
 
         // random points for each stat
@@ -173,120 +277,197 @@ impl StatSet {
         stats
     }
 
-    /// Returns the value of the given stat
-    pub fn value(&self, statid: StatId) -> isize {
-        match statid {
-            StatId::Constitution => self.constitution(),
-            StatId::Dexterity => self.dexterity(),
-            StatId::Strength => self.strength(),
-            StatId::Fortitude => self.fortitude(),
-            StatId::Aggression => self.aggression(),
-            StatId::Intellect => self.intellect(),
-        }
+    /// Sets the value of the given stat
+    pub fn set_value(&mut self, statid: StatId, value: isize) {
+        self.modify(statid, value - self.value(statid));
+    }
+
+    /// Gets the number of points that differ between two stat sets
+    pub fn distance(&self, other: &StatSet) -> isize {
+        StatId::ALL
+            .iter()
+            .map(|statid| (self.value(*statid) - other.value(*statid)).abs())
+            .sum()
+    }
+
+    /// Gets the total number of points across all stats
+    pub fn total(&self) -> isize {
+        StatId::ALL.iter().map(|statid| self.value(*statid)).sum()
     }
 
     /// Randomizes a single stat
-    pub fn randomize_stat(&mut self, statid: StatId) {
-        // TODO: not sure how to handle this,
-        // if the stat value changes we need to shuffle other stats around
-        match statid {
-            StatId::Constitution => {
-                // TODO:
-            }
-            StatId::Dexterity => {
-                // TODO:
-            }
-            StatId::Strength => {
-                // TODO:
-            }
-            StatId::Fortitude => {
-                // TODO:
-            }
-            StatId::Aggression => {
-                // TODO:
-            }
-            StatId::Intellect => {
-                // TODO:
+    ///
+    /// Strength is the fraction of the total points the stat may move by,
+    /// a strength of 1.0 or more re-rolls the stat entirely.
+    /// Points are moved between the other stats so the total is unchanged.
+    pub fn randomize_stat(&mut self, statid: StatId, strength: f32, random: &mut Random) {
+        let total = self.total();
+        if total <= 0 {
+            return;
+        }
+
+        let value = self.value(statid);
+        let new_value = if strength >= 1.0 {
+            random.random_range(0..=total)
+        } else {
+            let delta = ((total as f32 * strength).round() as isize).max(1);
+            (value + random.random_range(-delta..=delta)).clamp(0, total)
+        };
+
+        let others = StatId::ALL
+            .iter()
+            .copied()
+            .filter(|other| *other != statid)
+            .collect::<Vec<_>>();
+
+        // move points one at a time so no stat goes negative
+        let mut diff = new_value - value;
+        while diff > 0 {
+            let donors = others
+                .iter()
+                .copied()
+                .filter(|other| self.value(*other) > 0)
+                .collect::<Vec<_>>();
+            if donors.is_empty() {
+                break;
             }
+
+            let donor = donors[random.random_range(0..donors.len())];
+            self.modify(donor, -1);
+            self.modify(statid, 1);
+            diff -= 1;
+        }
+
+        while diff < 0 {
+            let recipient = others[random.random_range(0..others.len())];
+            self.modify(recipient, 1);
+            self.modify(statid, -1);
+            diff += 1;
         }
     }
 
-    /// Modifies a stat by amount
-    #[inline]
-    fn modify(&mut self, statid: StatId, amount: isize) {
-        match statid {
-            StatId::Constitution => {
-                self.constitution.value += amount;
-            }
-            StatId::Dexterity => {
-                self.dexterity.value += amount;
-            }
-            StatId::Strength => {
-                self.strength.value += amount;
-            }
-            StatId::Fortitude => {
-                self.fortitude.value += amount;
-            }
-            StatId::Aggression => {
-                self.aggression.value += amount;
-            }
-            StatId::Intellect => {
-                self.intellect.value += amount;
+    /// Rescales the stats so they total the given points
+    ///
+    /// Keeps the proportions between stats as close as possible,
+    /// rounding leftover points to the stats with the largest remainders.
+    /// Stats that total nothing are re-rolled.
+    pub fn normalize(&mut self, points: isize, random: &mut Random) {
+        for statid in StatId::ALL {
+            if self.value(statid) < 0 {
+                self.set_value(statid, 0);
             }
         }
+
+        let total = self.total();
+        if total == points {
+            return;
+        }
+
+        if total <= 0 {
+            *self = Self::random(points, random);
+            return;
+        }
+
+        let mut remainders = Vec::with_capacity(StatId::ALL.len());
+        for statid in StatId::ALL {
+            let scaled = self.value(statid) * points;
+            self.set_value(statid, scaled / total);
+            remainders.push((statid, scaled % total));
+        }
+
+        remainders.sort_by_key(|remainder| Reverse(remainder.1));
+        let leftover = (points - self.total()) as usize;
+        for (statid, _) in remainders.into_iter().take(leftover) {
+            self.modify(statid, 1);
+        }
     }
 
-    impl_stat!(constitution);
+    /// Gets the value derived from a stat with the given balance
+    pub fn derived(&self, statid: StatId, balance: &Balance) -> f64 {
+        (statid.definition().derived)(self.value(statid), balance)
+    }
 
     /// Gets the automata initial health, based on Constitution stat
     #[inline]
-    pub fn initial_health(&self) -> usize {
-        (BASE_HEALTH + (self.constitution() as f32 * CONSTITUTION_MOD) as isize).max(1) as usize
+    pub fn initial_health(&self, balance: &Balance) -> usize {
+        self.derived(StatId::Constitution, balance) as usize
     }
 
-    impl_stat!(dexterity);
-
     /// Gets the automata movement, based on Dexterity stat
     #[inline]
-    pub fn movement(&self) -> usize {
-        (BASE_MOVEMENT + (self.dexterity() as f32 * DEXTERITY_MOD) as isize).max(1) as usize
+    pub fn movement(&self, balance: &Balance) -> usize {
+        self.derived(StatId::Dexterity, balance) as usize
     }
 
-    impl_stat!(strength);
-
     /// Gets the automata attack damage, based on Strength stat
     #[inline]
-    pub fn attack_damage(&self) -> usize {
-        (BASE_ATTACK + (self.strength() as f32 * STRENGTH_MOD) as isize).max(1) as usize
+    pub fn attack_damage(&self, balance: &Balance) -> usize {
+        self.derived(StatId::Strength, balance) as usize
     }
 
-    impl_stat!(fortitude);
-
     /// Gets the amount of damage absorbed, based on Fortitude stat
     #[inline]
-    pub fn absorbed_damage(&self) -> usize {
-        (BASE_ATTACK_ABSORB + (self.fortitude() as f32 * FORTITUDE_MOD) as isize).max(1) as usize
+    pub fn absorbed_damage(&self, balance: &Balance) -> usize {
+        self.derived(StatId::Fortitude, balance) as usize
     }
 
-    impl_stat!(aggression);
-
     /// Gets the chance to move towards the enemy automata
     #[inline]
-    pub fn move_towards_enemy(&self, random: &mut Random) -> (bool, f64) {
-        let target =
-            (BASE_MOVE_TOWARDS_ENEMY + self.aggression() as f64 * AGGRESSION_MOD).clamp(0.0, 0.75);
+    pub fn move_towards_enemy(&self, balance: &Balance, random: &mut Random) -> (bool, f64) {
         let roll = random.random();
-        (roll < target, roll)
+        (roll < self.derived(StatId::Aggression, balance), roll)
     }
 
-    impl_stat!(intellect);
-
     /// Gets the chance to move towards food
     #[inline]
-    pub fn move_towards_food(&self, random: &mut Random) -> (bool, f64) {
-        let target =
-            (BASE_MOVE_TOWARDS_FOOD + self.intellect() as f64 * INTELLECT_MOD).clamp(0.0, 0.75);
+    pub fn move_towards_food(&self, balance: &Balance, random: &mut Random) -> (bool, f64) {
         let roll = random.random();
-        (roll < target, roll)
+        (roll < self.derived(StatId::Intellect, balance), roll)
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    /// Stat points budget the tests use
+    pub(crate) const POINTS: isize = 50;
+
+    /// Checks the stats spend exactly the budget, with no negative stat
+    pub(crate) fn assert_budget(stats: &StatSet) {
+        assert_eq!(stats.total(), POINTS);
+        for statid in StatId::ALL {
+            assert!(stats.value(statid) >= 0, "{} is negative", statid.name());
+        }
+    }
+
+    #[test]
+    fn normalize_keeps_budget() {
+        let mut random = Random::new(0);
+        for _ in 0..100 {
+            let mut stats = StatSet::random(POINTS * 2, &mut random);
+            stats.set_value(StatId::Strength, -5);
+            stats.normalize(POINTS, &mut random);
+            assert_budget(&stats);
+        }
+
+        // empty stats are re-rolled
+        let mut stats = StatSet::default();
+        stats.normalize(POINTS, &mut random);
+        assert_budget(&stats);
+    }
+
+    #[test]
+    fn randomize_stat_keeps_budget() {
+        let mut random = Random::new(0);
+        for strength in [0.05, 0.5, 1.0, 2.0] {
+            for _ in 0..100 {
+                let mut stats = StatSet::random(POINTS, &mut random);
+                for statid in StatId::ALL {
+                    stats.randomize_stat(statid, strength, &mut random);
+                    assert_budget(&stats);
+                }
+            }
+        }
     }
 }
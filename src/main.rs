@@ -14,10 +14,12 @@ mod bundles;
 mod components;
 mod events;
 mod game;
+mod options;
 mod plugins;
 mod resources;
 mod states;
 mod systems;
+mod training;
 mod util;
 
 use bevy::diagnostic::*;
@@ -26,6 +28,10 @@ use bevy::window::PresentMode;
 use bevy_egui::{EguiPlugin, EguiSettings};
 use bevy_inspector_egui::{RegisterInspectable, WorldInspectorParams, WorldInspectorPlugin};
 
+use game::persist::*;
+use game::replay::*;
+use options::*;
+use plugins::balance::*;
 use plugins::debug::*;
 use plugins::states::*;
 use plugins::ui::*;
@@ -55,15 +61,30 @@ pub const CELL_Y_SCALE: f32 = ((GRID_HEIGHT as f32 * BASE_CELL_HEIGHT) - TOP_MAR
 pub const CELL_WIDTH: f32 = BASE_CELL_WIDTH * CELL_X_SCALE * ASPECT_RATIO;
 pub const CELL_HEIGHT: f32 = BASE_CELL_HEIGHT * CELL_Y_SCALE;
 
-pub const ROUNDS: usize = 10;
-pub const STAT_POINTS: isize = 50;
+pub const POPULATION_SIZE: usize = 20;
+pub const PLAYER_HISTORY_SIZE: usize = 5;
+pub const HALL_OF_FAME_SIZE: usize = 10;
+pub const POPULATION_PATH: &str = "population.ron";
+
+pub const ASSET_DIR: &str = "assets";
+pub const BALANCE_ASSET: &str = "game.balance.ron";
+
+/// Directory match replays are saved to
+pub const REPLAY_DIR: &str = "replays";
+
+/// Seconds between simulation turns at normal speed
+pub const TURN_INTERVAL: f32 = 0.25;
 
 /// Initial setup
-fn setup(mut commands: Commands, asset_server: Res<AssetServer>) {
+fn setup(mut commands: Commands, options: Res<Options>, asset_server: Res<AssetServer>) {
     #[cfg(debug_assertions)]
     asset_server.watch_for_changes().unwrap();
 
-    let random = Random::default();
+    let random = match options.seed {
+        Some(seed) => Random::new(seed),
+        None => Random::default(),
+    };
+    info!("Random seed: {}", random.seed());
 
     // assets
     let fonts = Fonts {
@@ -71,11 +92,16 @@ fn setup(mut commands: Commands, asset_server: Res<AssetServer>) {
     };
     commands.insert_resource(fonts);
 
+    commands.insert_resource(resources::balance::BalanceAsset {
+        handle: asset_server.load(BALANCE_ASSET),
+    });
+
     // materials
     let automata_materials = resources::automata::AutomataColors {
         cell: Color::BISQUE,
         player_automata: Color::TEAL,
         ai_automata: Color::ORANGE_RED,
+        food: Color::LIME_GREEN,
     };
     commands.insert_resource(automata_materials);
 
@@ -87,6 +113,9 @@ fn setup(mut commands: Commands, asset_server: Res<AssetServer>) {
     };
     commands.insert_resource(button_materials);
 
+    commands.insert_resource(resources::game::SimulationClock::new(TURN_INTERVAL));
+    commands.insert_resource(resources::battlelog::BattleLog::default());
+
     commands.insert_resource(random);
 }
 
@@ -97,6 +126,35 @@ fn main() {
         error!(%data, "Unexpected panic!");
     }));
 
+    let options = match Options::from_env() {
+        Ok(options) => options,
+        Err(err) => {
+            eprintln!("{}", err);
+            std::process::exit(1);
+        }
+    };
+
+    // training runs headless instead of the game
+    if let Some(generations) = options.train {
+        if let Err(err) = training::train(&options, generations) {
+            eprintln!("Training failed: {}", err);
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    // replays are loaded up front so a bad file fails early
+    let replay = options
+        .replay
+        .as_ref()
+        .map(|path| match Replay::load(path) {
+            Ok(replay) => replay,
+            Err(err) => {
+                eprintln!("Failed to load replay {}: {}", path.display(), err);
+                std::process::exit(1);
+            }
+        });
+
     let mut app = App::new();
 
     // basic bevy
@@ -137,6 +195,7 @@ fn main() {
     .register_inspectable::<components::automata::PlayerAutomata>()
     .register_inspectable::<components::automata::AIAutomata>()
     .register_inspectable::<components::gridworld::GridWorldCell>()
+    .register_inspectable::<components::gridworld::Food>()
     .register_inspectable::<components::ui::ButtonHelper>()
     .register_inspectable::<components::ui::ActionButton>()
     .register_inspectable::<components::ui::StatModifierButton>()
@@ -148,15 +207,19 @@ fn main() {
     .register_inspectable::<game::dna::Dna>();
 
     // plugins
-    app.add_plugin(DebugPlugin)
+    app.add_plugin(BalancePlugin)
+        .add_plugin(DebugPlugin)
         .add_plugin(UIPlugin)
         .add_plugins(StatesPlugins);
 
-    // initial game state
-    app.add_state(GameState::Intro);
+    // initial game state, which moves on to the replay if there is one
+    if let Some(replay) = replay {
+        app.insert_resource(replay);
+    }
+    app.add_state(GameState::Loading);
 
     // main setup
-    app.add_startup_system(setup);
+    app.insert_resource(options).add_startup_system(setup);
 
     app.run();
 }
@@ -0,0 +1,149 @@
+//! Command line options
+
+use std::path::PathBuf;
+
+use crate::game::balance::*;
+use crate::resources::automata::*;
+use crate::training::*;
+
+/// Environment variable used to set the random seed
+const SEED_ENV: &str = "REMIX_SEED";
+
+/// Command line options
+#[derive(Debug, Default)]
+pub struct Options {
+    /// Random seed for the whole run
+    pub seed: Option<u64>,
+
+    /// Replay file to play back instead of playing the game
+    pub replay: Option<PathBuf>,
+
+    /// Number of generations to train the AI population for
+    /// instead of playing the game
+    pub train: Option<usize>,
+
+    /// AI population file training saves and the game starts from
+    pub population: Option<PathBuf>,
+
+    /// Number of training islands evolving in parallel
+    pub islands: Option<usize>,
+
+    /// Generations between training island migrations
+    pub migration_interval: Option<usize>,
+
+    /// Number of DNA each training island sends per migration
+    pub migrants: Option<usize>,
+
+    /// Training island migration topology
+    pub topology: Option<MigrationTopology>,
+
+    /// Evolve neural network controllers in new AI populations
+    pub neural: bool,
+}
+
+impl Options {
+    /// Parses the options from the environment and command line
+    ///
+    /// Command line arguments take precedence over environment variables
+    pub fn from_env() -> Result<Self, String> {
+        let mut options = Self::default();
+
+        if let Ok(seed) = std::env::var(SEED_ENV) {
+            options.seed = Some(parse_seed(&seed)?);
+        }
+
+        let mut args = std::env::args().skip(1);
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--seed" => {
+                    let seed = args.next().ok_or("--seed requires a value")?;
+                    options.seed = Some(parse_seed(&seed)?);
+                }
+                "--replay" => {
+                    let replay = args.next().ok_or("--replay requires a file")?;
+                    options.replay = Some(replay.into());
+                }
+                "--train" => {
+                    let generations = args.next().ok_or("--train requires a generation count")?;
+                    options.train = Some(parse_count(&arg, &generations)?);
+                }
+                "--islands" => {
+                    let islands = args.next().ok_or("--islands requires a count")?;
+                    options.islands = Some(parse_count(&arg, &islands)?);
+                }
+                "--migration-interval" => {
+                    let interval = args
+                        .next()
+                        .ok_or("--migration-interval requires a generation count")?;
+                    options.migration_interval = Some(parse_count(&arg, &interval)?);
+                }
+                "--migrants" => {
+                    let migrants = args.next().ok_or("--migrants requires a count")?;
+                    options.migrants = Some(parse_count(&arg, &migrants)?);
+                }
+                "--topology" => {
+                    let topology = args
+                        .next()
+                        .ok_or("--topology requires one of ring, full or random")?;
+                    options.topology = Some(topology.parse()?);
+                }
+                "--neural" => options.neural = true,
+                "--population" => {
+                    let population = args.next().ok_or("--population requires a file")?;
+                    options.population = Some(population.into());
+                }
+                _ => return Err(format!("Unknown argument: {}", arg)),
+            }
+        }
+
+        Ok(options)
+    }
+
+    /// Checks if the game loads and saves the AI population
+    ///
+    /// Seeded runs don't, so the same seed always plays the same game
+    pub fn persistent(&self) -> bool {
+        self.seed.is_none()
+    }
+
+    /// Gets the configuration for new AI populations
+    pub fn population_config(&self, balance: &Balance) -> PopulationConfig {
+        PopulationConfig {
+            min_mutation_rate: balance.min_mutation_rate,
+            max_mutation_rate: balance.max_mutation_rate,
+            mutation_strength: balance.mutation_strength,
+            neural: self.neural,
+            ..Default::default()
+        }
+    }
+
+    /// Gets the AI population file training loads and saves
+    pub fn population_path(&self) -> PathBuf {
+        self.population
+            .clone()
+            .unwrap_or_else(|| crate::POPULATION_PATH.into())
+    }
+
+    /// Gets the AI population file the game starts from, if any
+    ///
+    /// An explicit population file is loaded even in seeded runs,
+    /// loading a fixed file keeps the run reproducible
+    pub fn game_population_path(&self) -> Option<PathBuf> {
+        match &self.population {
+            Some(population) => Some(population.clone()),
+            None if self.persistent() => Some(crate::POPULATION_PATH.into()),
+            None => None,
+        }
+    }
+}
+
+fn parse_seed(seed: &str) -> Result<u64, String> {
+    seed.parse()
+        .map_err(|err| format!("Invalid seed '{}': {}", seed, err))
+}
+
+fn parse_count(arg: &str, count: &str) -> Result<usize, String> {
+    count
+        .parse()
+        .map_err(|err| format!("Invalid {} count '{}': {}", arg, count, err))
+}
@@ -0,0 +1,64 @@
+//! Balance plugin
+
+use bevy::asset::{AssetLoader, LoadContext, LoadedAsset};
+use bevy::prelude::*;
+use bevy::utils::BoxedFuture;
+
+use crate::game::balance::*;
+use crate::game::persist::*;
+use crate::systems::balance::*;
+
+/// Balance asset loader
+#[derive(Default)]
+struct BalanceLoader;
+
+impl AssetLoader for BalanceLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), bevy::asset::Error>> {
+        Box::pin(async move {
+            let data = std::str::from_utf8(bytes)?;
+            let balance = Balance::from_str(data, Format::Ron)
+                .map_err(|err| bevy::asset::Error::msg(err.to_string()))?;
+
+            load_context.set_default_asset(LoadedAsset::new(balance));
+
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["balance.ron"]
+    }
+}
+
+/// Balance plugin
+pub struct BalancePlugin;
+
+impl Plugin for BalancePlugin {
+    fn build(&self, app: &mut App) {
+        // assets
+        app.add_asset::<Balance>()
+            .init_asset_loader::<BalanceLoader>();
+
+        // systems
+        app.add_system(balance_asset_event_handler);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn loader_matches_asset() {
+        // the asset server tries each suffix after a dot in the file name
+        let asset = crate::BALANCE_ASSET;
+        let extensions = BalanceLoader.extensions();
+        assert!(asset
+            .match_indices('.')
+            .any(|(idx, _)| extensions.contains(&&asset[idx + 1..])));
+    }
+}
@@ -17,6 +17,8 @@ impl Plugin for DebugPlugin {
         app.add_startup_system(setup_debug);
 
         // systems
-        app.add_system(debug_system).add_system(debug_ui);
+        app.add_system(debug_system)
+            .add_system(debug_ui)
+            .add_system(evolution_ui);
     }
 }
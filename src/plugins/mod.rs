@@ -1,5 +1,6 @@
 //! ECS plugins
 
+pub mod balance;
 pub mod debug;
 pub mod states;
 pub mod ui;
@@ -14,10 +14,24 @@ pub struct StatesPlugins;
 impl PluginGroup for StatesPlugins {
     fn build(&mut self, group: &mut PluginGroupBuilder) {
         group
+            .add(LoadingStatePlugin)
             .add(IntroStatePlugin)
             .add(RemixStatePlugin)
             .add(GameStatePlugin)
-            .add(GameOverStatePlugin);
+            .add(GameOverStatePlugin)
+            .add(ReplayStatePlugin);
+    }
+}
+
+/// Loading state plugin
+struct LoadingStatePlugin;
+
+impl Plugin for LoadingStatePlugin {
+    fn build(&self, app: &mut App) {
+        // systems
+        app.add_system_set(
+            SystemSet::on_update(GameState::Loading).with_system(states::loading::balance_loaded),
+        );
     }
 }
 
@@ -76,7 +90,10 @@ impl Plugin for GameStatePlugin {
                     .with_system(states::game::cell_selection_button_handler)
                     .with_system(states::game::game_start_event_handler)
                     .with_system(states::game::health_changed_event_handler)
-                    .with_system(states::game::automata_action),
+                    .with_system(states::game::automata_action)
+                    .with_system(states::game::simulation_controls_ui)
+                    .with_system(states::game::battle_log_ui)
+                    .with_system(states::game::round_end),
             )
             .add_system_set(
                 SystemSet::on_exit(GameState::Game).with_system(states::game::teardown),
@@ -102,3 +119,24 @@ impl Plugin for GameOverStatePlugin {
         );
     }
 }
+
+/// Replay state plugin
+struct ReplayStatePlugin;
+
+impl Plugin for ReplayStatePlugin {
+    fn build(&self, app: &mut App) {
+        // systems
+        app.add_system_set(
+            SystemSet::on_enter(GameState::Replay).with_system(states::replay::setup),
+        )
+        .add_system_set(
+            SystemSet::on_update(GameState::Replay)
+                .with_system(states::replay::playback)
+                .with_system(states::replay::replay_controls_ui)
+                .with_system(states::game::health_changed_event_handler),
+        )
+        .add_system_set(
+            SystemSet::on_exit(GameState::Replay).with_system(states::replay::teardown),
+        );
+    }
+}
@@ -1,8 +1,15 @@
 //! Automata resources
 
 use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
 
+use crate::game::balance::*;
 use crate::game::dna::*;
+use crate::game::halloffame::*;
+use crate::game::pareto::*;
+use crate::game::persist::*;
+use crate::game::simulation::*;
+use crate::game::species::*;
 use crate::game::stats::*;
 use crate::resources::*;
 
@@ -15,74 +22,36 @@ pub trait AutomataStats {
     fn modify(&mut self, statid: StatId, amount: isize) -> bool;
 }
 
-macro_rules! impl_modify_stats {
-    () => {
-        fn modify(&mut self, statid: StatId, amount: isize) -> bool {
-            if self.points - amount < 0 {
-                return false;
-            }
-
-            match statid {
-                StatId::Constitution => {
-                    if self.stats.constitution() + amount < 0 {
-                        return false;
-                    }
-                    self.stats
-                        .set_constitution(self.stats.constitution() + amount);
-                }
-                StatId::Dexterity => {
-                    if self.stats.dexterity() + amount < 0 {
-                        return false;
-                    }
-                    self.stats.set_dexterity(self.stats.dexterity() + amount);
-                }
-                StatId::Strength => {
-                    if self.stats.strength() + amount < 0 {
-                        return false;
-                    }
-                    self.stats.set_strength(self.stats.strength() + amount);
-                }
-                StatId::Fortitude => {
-                    if self.stats.fortitude() + amount < 0 {
-                        return false;
-                    }
-                    self.stats.set_fortitude(self.stats.fortitude() + amount);
-                }
-                StatId::Aggression => {
-                    if self.stats.aggression() + amount < 0 {
-                        return false;
-                    }
-                    self.stats.set_aggression(self.stats.aggression() + amount);
-                }
-                StatId::Intellect => {
-                    if self.stats.intellect() + amount < 0 {
-                        return false;
-                    }
-                    self.stats.set_intellect(self.stats.intellect() + amount);
-                }
-            }
-
-            self.points -= amount;
-
-            true
-        }
-    };
-}
-
 /// Player automata stats
-#[derive(Debug, Default, Clone, Copy)]
+#[derive(Debug, Default, Clone, Copy, Serialize, Deserialize)]
 pub struct PlayerAutomataStats {
     points: isize,
 
     pub stats: StatSet,
 }
 
+impl Persist for PlayerAutomataStats {}
+
 impl AutomataStats for PlayerAutomataStats {
     fn stats(&self) -> &StatSet {
         &self.stats
     }
 
-    impl_modify_stats!();
+    fn modify(&mut self, statid: StatId, amount: isize) -> bool {
+        if self.points - amount < 0 {
+            return false;
+        }
+
+        let value = self.stats.value(statid) + amount;
+        if value < 0 {
+            return false;
+        }
+        self.stats.set_value(statid, value);
+
+        self.points -= amount;
+
+        true
+    }
 }
 
 impl PlayerAutomataStats {
@@ -101,76 +70,608 @@ impl PlayerAutomataStats {
 
     /// Gets the value of a stat
     pub fn value(&self, statid: StatId) -> isize {
-        match statid {
-            StatId::Constitution => self.stats.constitution(),
-            StatId::Dexterity => self.stats.dexterity(),
-            StatId::Strength => self.stats.strength(),
-            StatId::Fortitude => self.stats.fortitude(),
-            StatId::Aggression => self.stats.aggression(),
-            StatId::Intellect => self.stats.intellect(),
-        }
+        self.stats.value(statid)
     }
 }
 
-/// AI automata stats
-#[derive(Debug, Default, Clone, Copy)]
-pub struct AIAutomataStats {
-    points: isize,
+/// AI automata population genetic algorithm configuration
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PopulationConfig {
+    /// Number of individuals in a generation
+    pub size: usize,
 
-    pub stats: StatSet,
-}
+    /// Bounds of the mutation rate as it adapts to the population diversity
+    pub min_mutation_rate: f64,
+    pub max_mutation_rate: f64,
 
-impl AutomataStats for AIAutomataStats {
-    fn stats(&self) -> &StatSet {
-        &self.stats
-    }
+    pub mutation_strength: f32,
+
+    /// Number of the fittest DNA copied unchanged into the next generation
+    pub elites: usize,
+
+    pub selection: SelectionMethod,
+    pub crossover: CrossoverMethod,
 
-    impl_modify_stats!();
+    /// Share fitness within species and draw round opponents from each species
+    pub speciation: bool,
+
+    /// Stat distance within which DNA belongs to the same species
+    pub species_distance: isize,
+
+    /// Evolve neural network controllers along with the stats
+    pub neural: bool,
 }
 
-impl AIAutomataStats {
-    /// Creates new, randomized AI automata stats
-    pub fn new(points: isize, random: &mut Random) -> Self {
+impl Default for PopulationConfig {
+    fn default() -> Self {
         Self {
-            points,
-            stats: StatSet::random(points, random),
+            size: crate::POPULATION_SIZE,
+            min_mutation_rate: Balance::DEFAULT.min_mutation_rate,
+            max_mutation_rate: Balance::DEFAULT.max_mutation_rate,
+            mutation_strength: Balance::DEFAULT.mutation_strength,
+            elites: ELITE_COUNT,
+            selection: SelectionMethod::Tournament(TOURNAMENT_SIZE),
+            crossover: CrossoverMethod::Uniform,
+            speciation: true,
+            species_distance: SPECIES_DISTANCE,
+            neural: false,
         }
     }
 }
 
+/// Fitness statistics of an evaluated generation
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct GenerationStats {
+    pub generation: usize,
+
+    pub best: f32,
+    pub mean: f32,
+    pub worst: f32,
+
+    pub mutation_rate: f64,
+    pub diversity: f64,
+}
+
 /// AI automata population
-#[derive(Debug)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct AIAutomataPopulation {
+    config: PopulationConfig,
+    points: isize,
+
     mutation_rate: f64,
 
-    population: Vec<AIAutomataStats>,
+    generation: usize,
+    population: Vec<Dna>,
+
+    #[serde(skip)]
     mating_pool: Vec<Dna>,
+
+    /// The fittest DNA ever seen, saved with the population
+    hall_of_fame: HallOfFame,
+
+    /// The player stats from past rounds, most recent last
+    player_history: Vec<StatSet>,
+
+    /// The last evaluated generation
+    #[serde(default)]
+    evaluated: Vec<Dna>,
+
+    /// The non-dominated DNA of the last evaluated generation
+    #[serde(default)]
+    pareto_front: Vec<Dna>,
+
+    /// The species of the last evaluated generation, fittest first
+    #[serde(default)]
+    species: Vec<Species>,
+
+    /// Fitness statistics of each evaluated generation
+    #[serde(default)]
+    history: Vec<GenerationStats>,
 }
 
+impl Persist for AIAutomataPopulation {}
+
 impl AIAutomataPopulation {
     /// Creates a new randomized population
-    pub fn new(mutation_rate: f64, rounds: usize, points: isize, random: &mut Random) -> Self {
-        let mut population = Vec::with_capacity(rounds);
+    pub fn new(
+        config: PopulationConfig,
+        rounds: usize,
+        points: isize,
+        random: &mut Random,
+    ) -> Self {
+        let mut population = Vec::with_capacity(config.size);
         for _ in 0..population.capacity() {
-            population.push(AIAutomataStats::new(points, random));
+            population.push(if config.neural {
+                Dna::with_brain(rounds, points, random)
+            } else {
+                Dna::new(rounds, points, random)
+            });
         }
 
-        Self {
-            mutation_rate,
+        let (species, _) = speciate(&population, config.species_distance);
+
+        let mut ai_population = Self {
+            config,
+            points,
+            mutation_rate: config.max_mutation_rate,
+            generation: 0,
             population,
             mating_pool: vec![],
+            hall_of_fame: HallOfFame::new(crate::HALL_OF_FAME_SIZE),
+            player_history: vec![],
+            evaluated: vec![],
+            pareto_front: vec![],
+            species,
+            history: vec![],
+        };
+        ai_population.adapt_mutation_rate();
+
+        ai_population
+    }
+
+    /// Gets the hall of fame
+    #[inline]
+    pub fn hall_of_fame(&self) -> &HallOfFame {
+        &self.hall_of_fame
+    }
+
+    /// Applies the balance mutation settings to a loaded population
+    pub fn apply_balance(&mut self, balance: &Balance) {
+        self.config.min_mutation_rate = balance.min_mutation_rate;
+        self.config.max_mutation_rate = balance.max_mutation_rate;
+        self.config.mutation_strength = balance.mutation_strength;
+
+        self.adapt_mutation_rate();
+    }
+
+    /// Gets the genetic algorithm configuration
+    #[inline]
+    pub fn config(&self) -> &PopulationConfig {
+        &self.config
+    }
+
+    /// Gets the number of stat points each DNA has
+    #[inline]
+    pub fn points(&self) -> isize {
+        self.points
+    }
+
+    /// Gets the current mutation rate
+    #[inline]
+    pub fn mutation_rate(&self) -> f64 {
+        self.mutation_rate
+    }
+
+    /// Gets the variance of a stat across the population
+    pub fn variance(&self, statid: StatId) -> f64 {
+        if self.population.is_empty() {
+            return 0.0;
+        }
+
+        let count = self.population.len() as f64;
+        let mean = self
+            .population
+            .iter()
+            .map(|dna| dna.genes().value(statid) as f64)
+            .sum::<f64>()
+            / count;
+
+        self.population
+            .iter()
+            .map(|dna| (dna.genes().value(statid) as f64 - mean).powi(2))
+            .sum::<f64>()
+            / count
+    }
+
+    /// Gets the genetic diversity of the population in the range [0..1]
+    ///
+    /// This is the mean standard deviation of each stat,
+    /// relative to the largest possible standard deviation
+    pub fn diversity(&self) -> f64 {
+        let max_std_dev = self.points as f64 / 2.0;
+        if max_std_dev <= 0.0 {
+            return 0.0;
         }
+
+        let std_dev = StatId::ALL
+            .iter()
+            .map(|statid| self.variance(*statid).sqrt())
+            .sum::<f64>()
+            / StatId::ALL.len() as f64;
+
+        (std_dev / max_std_dev).clamp(0.0, 1.0)
+    }
+
+    /// Adapts the mutation rate to the population diversity
+    ///
+    /// A converged population mutates at the max rate,
+    /// a fully diverse one at the min rate
+    fn adapt_mutation_rate(&mut self) {
+        let min = self.config.min_mutation_rate;
+        let max = self.config.max_mutation_rate.max(min);
+
+        self.mutation_rate = max - (max - min) * self.diversity();
+    }
+
+    /// Gets the current generation number
+    #[inline]
+    pub fn generation(&self) -> usize {
+        self.generation
+    }
+
+    /// Gets the current generation
+    #[inline]
+    pub fn population(&self) -> &[Dna] {
+        &self.population
+    }
+
+    /// Gets the non-dominated DNA of the last evaluated generation
+    ///
+    /// These are the distinct opponent styles, such as tanks,
+    /// glass cannons and foragers
+    #[inline]
+    pub fn pareto_front(&self) -> &[Dna] {
+        &self.pareto_front
     }
 
-    pub fn round_stats(&self, round: usize) -> &AIAutomataStats {
-        self.population.get(round).unwrap()
+    /// Gets the fitness statistics of each evaluated generation
+    #[inline]
+    pub fn history(&self) -> &[GenerationStats] {
+        &self.history
+    }
+
+    /// Gets the population sorted by total fitness, fittest first
+    pub fn sorted_by_fitness(&self) -> Vec<&Dna> {
+        sort_by_fitness(&self.population)
+    }
+
+    /// Gets the last evaluated generation sorted by total fitness, fittest first
+    ///
+    /// Unlike the current generation, every DNA in it has a measured fitness
+    pub fn evaluated_by_fitness(&self) -> Vec<&Dna> {
+        sort_by_fitness(&self.evaluated)
+    }
+
+    /// Gets copies of the fittest DNA to migrate to another population
+    pub fn emigrants(&self, count: usize) -> Vec<Dna> {
+        self.sorted_by_fitness()
+            .into_iter()
+            .take(count)
+            .cloned()
+            .collect()
+    }
+
+    /// Adds migrant DNA from another population
+    ///
+    /// Each migrant replaces the least fit DNA, if it is fitter
+    pub fn immigrate(&mut self, mut migrants: Vec<Dna>) {
+        migrants.sort_by(|a, b| b.total_fitness().total_cmp(&a.total_fitness()));
+
+        for migrant in migrants {
+            let worst = self
+                .population
+                .iter()
+                .enumerate()
+                .min_by(|a, b| a.1.total_fitness().total_cmp(&b.1.total_fitness()))
+                .map(|(idx, _)| idx);
+
+            match worst {
+                Some(idx) if self.population[idx].total_fitness() < migrant.total_fitness() => {
+                    self.population[idx] = migrant;
+                }
+                _ => break,
+            }
+        }
+    }
+
+    /// Gets the species of the last evaluated generation, fittest first
+    #[inline]
+    pub fn species(&self) -> &[Species] {
+        &self.species
+    }
+
+    /// Gets the distinct opponent styles the round opponents are drawn from
+    ///
+    /// These are the species champions, with speciation,
+    /// followed by the Pareto front of the last evaluated generation
+    pub fn opponent_candidates(&self) -> Vec<&Dna> {
+        let mut candidates: Vec<&Dna> = vec![];
+        if self.config.speciation {
+            candidates.extend(self.species.iter().map(|species| &species.champion));
+        }
+
+        for dna in self.pareto_front.iter() {
+            if !candidates
+                .iter()
+                .any(|candidate| candidate.genes() == dna.genes())
+            {
+                candidates.push(dna);
+            }
+        }
+
+        candidates
+    }
+
+    /// Gets the DNA the AI plays a round with
+    ///
+    /// Picks the candidate farthest from its closest earlier opponent,
+    /// so consecutive rounds draw different styles.
+    /// Ties go to the earlier, fitter candidate.
+    pub fn round_opponent(&self, previous: &[StatSet]) -> &Dna {
+        let candidates = self.opponent_candidates();
+        if candidates.is_empty() {
+            return &self.population[previous.len() % self.population.len()];
+        }
+
+        let mut best = candidates[0];
+        let mut best_distance = isize::MIN;
+        for candidate in candidates {
+            let distance = previous
+                .iter()
+                .map(|opponent| opponent.distance(candidate.genes()))
+                .min()
+                .unwrap_or(isize::MAX);
+            if distance > best_distance {
+                best = candidate;
+                best_distance = distance;
+            }
+        }
+
+        best
+    }
+
+    /// Gets the player stats from past rounds, most recent last
+    #[inline]
+    pub fn player_history(&self) -> &[StatSet] {
+        &self.player_history
+    }
+
+    /// Records the player stats from a round
+    ///
+    /// Only the most recent PLAYER_HISTORY_SIZE rounds are kept
+    pub fn record_player(&mut self, player: StatSet) {
+        self.player_history.push(player);
+
+        let excess = self
+            .player_history
+            .len()
+            .saturating_sub(crate::PLAYER_HISTORY_SIZE);
+        self.player_history.drain(..excess);
+    }
+
+    /// Measures the fitness of the population against the player history
+    pub fn evaluate(
+        &mut self,
+        width: usize,
+        height: usize,
+        balance: &Balance,
+        random: &mut Random,
+    ) {
+        let opponents = self.player_history.clone();
+        self.evaluate_against(&opponents, width, height, balance, random);
+    }
+
+    /// Measures the fitness of the population against a set of opponents
+    ///
+    /// Each individual plays a headless match against every opponent,
+    /// its fitness is the mean of those matches
+    pub fn evaluate_against(
+        &mut self,
+        opponents: &[StatSet],
+        width: usize,
+        height: usize,
+        balance: &Balance,
+        random: &mut Random,
+    ) {
+        if opponents.is_empty() {
+            return;
+        }
+
+        for dna in self.population.iter_mut() {
+            let genes = *dna.genes();
+            let brain = dna.brain().cloned();
+
+            let fitnesses = opponents
+                .iter()
+                .map(|player| {
+                    let simulation = Simulation::new(
+                        *player,
+                        genes,
+                        width,
+                        height,
+                        *balance,
+                        Random::new(random.random_range(0..u64::MAX)),
+                    )
+                    .with_ai_brain(brain.clone());
+                    let result = simulation.run();
+
+                    StatSetFitness::new(result.ai_health, &result.ai_metrics)
+                })
+                .collect::<Vec<_>>();
+
+            dna.set_fitness(StatSetFitness::mean(&fitnesses));
+        }
+
+        self.hall_of_fame.record(self.generation, &self.population);
+        self.evaluated = self.population.clone();
+    }
+
+    /// Breeds the next generation from the fitness of the current one
+    pub fn next_generation(&mut self, random: &mut Random) {
+        self.adapt_mutation_rate();
+
+        self.record_history();
+
+        let fitnesses = self
+            .population
+            .iter()
+            .map(|dna| *dna.stat_fitness())
+            .collect::<Vec<_>>();
+        let pareto = ParetoRanking::new(&fitnesses);
+
+        // fitness is shared between the members of a species
+        let (species, membership) = speciate(&self.population, self.config.species_distance);
+        let shared = self
+            .population
+            .iter()
+            .zip(membership.iter())
+            .map(|(dna, idx)| {
+                if self.config.speciation {
+                    dna.total_fitness() / species[*idx].size as f32
+                } else {
+                    dna.total_fitness()
+                }
+            })
+            .collect::<Vec<_>>();
+        self.species = species;
+
+        self.pareto_front = pareto
+            .fronts
+            .first()
+            .map(|front| {
+                front
+                    .iter()
+                    .map(|idx| self.population[*idx].clone())
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        let mut children = Vec::with_capacity(self.population.len());
+
+        // elites survive unchanged
+        let ranked = if self.config.selection == SelectionMethod::Pareto {
+            pareto.sorted()
+        } else {
+            let mut ranked = (0..self.population.len()).collect::<Vec<_>>();
+            ranked.sort_by(|a, b| {
+                self.population[*b]
+                    .total_fitness()
+                    .total_cmp(&self.population[*a].total_fitness())
+            });
+            ranked
+        };
+        for idx in ranked.into_iter().take(self.config.elites) {
+            children.push(self.population[idx].clone());
+        }
+
+        // fill the mating pool with a pair of parents for each bred child
+        self.mating_pool.clear();
+        for _ in 0..(self.population.len() - children.len()) * 2 {
+            let idx = self.select(&shared, &pareto, random);
+            self.mating_pool.push(self.population[idx].clone());
+        }
+
+        for parents in self.mating_pool.chunks(2) {
+            let mut child = parents[0].crossover(&parents[1], self.config.crossover, random);
+            child.mutate(self.mutation_rate, self.config.mutation_strength, random);
+            children.push(child);
+        }
+
+        self.population = children;
+        self.generation += 1;
+
+        debug!(
+            "Bred generation {} (mutation rate {:.3})",
+            self.generation, self.mutation_rate
+        );
+    }
+
+    /// Records the fitness statistics of the current generation
+    fn record_history(&mut self) {
+        let totals = self
+            .population
+            .iter()
+            .map(|dna| dna.total_fitness())
+            .collect::<Vec<_>>();
+        if totals.is_empty() {
+            return;
+        }
+
+        self.history.push(GenerationStats {
+            generation: self.generation,
+            best: totals.iter().copied().fold(f32::MIN, f32::max),
+            mean: totals.iter().sum::<f32>() / totals.len() as f32,
+            worst: totals.iter().copied().fold(f32::MAX, f32::min),
+            mutation_rate: self.mutation_rate,
+            diversity: self.diversity(),
+        });
+    }
+
+    /// Selects a parent index by fitness
+    fn select(&self, fitness: &[f32], pareto: &ParetoRanking, random: &mut Random) -> usize {
+        match self.config.selection {
+            SelectionMethod::Roulette => {
+                let weights = fitness.iter().map(|f| *f as f64).collect::<Vec<_>>();
+                Self::spin(&weights, random)
+            }
+            SelectionMethod::Tournament(size) => {
+                let mut best = random.random_range(0..self.population.len());
+                for _ in 1..size.max(1) {
+                    let idx = random.random_range(0..self.population.len());
+                    if fitness[idx] > fitness[best] {
+                        best = idx;
+                    }
+                }
+                best
+            }
+            SelectionMethod::Rank => {
+                let mut ranked = (0..self.population.len()).collect::<Vec<_>>();
+                ranked.sort_by(|a, b| fitness[*a].total_cmp(&fitness[*b]));
+
+                // the least fit individual has rank 1
+                let mut weights = vec![0.0; self.population.len()];
+                for (rank, idx) in ranked.iter().enumerate() {
+                    weights[*idx] = (rank + 1) as f64;
+                }
+                Self::spin(&weights, random)
+            }
+            SelectionMethod::Pareto => {
+                let a = random.random_range(0..self.population.len());
+                let b = random.random_range(0..self.population.len());
+                if pareto.better(b, a) {
+                    b
+                } else {
+                    a
+                }
+            }
+        }
+    }
+
+    /// Spins a roulette wheel with the given weights
+    ///
+    /// Falls back to a uniform pick if there is no weight
+    fn spin(weights: &[f64], random: &mut Random) -> usize {
+        let total = weights.iter().sum::<f64>();
+        if total <= 0.0 {
+            return random.random_range(0..weights.len());
+        }
+
+        let mut spin = random.random() * total;
+        for (idx, weight) in weights.iter().enumerate() {
+            if spin < *weight {
+                return idx;
+            }
+            spin -= weight;
+        }
+
+        weights.len() - 1
     }
 }
 
+/// Sorts DNA by total fitness, fittest first
+fn sort_by_fitness(population: &[Dna]) -> Vec<&Dna> {
+    let mut sorted = population.iter().collect::<Vec<_>>();
+    sorted.sort_by(|a, b| b.total_fitness().total_cmp(&a.total_fitness()));
+    sorted
+}
+
 /// Automata colors container resource
 pub struct AutomataColors {
     pub cell: Color,
 
     pub player_automata: Color,
     pub ai_automata: Color,
+
+    pub food: Color,
 }
@@ -0,0 +1,10 @@
+//! Balance resources
+
+use bevy::prelude::*;
+
+use crate::game::balance::*;
+
+/// Balance asset container resource
+pub struct BalanceAsset {
+    pub handle: Handle<Balance>,
+}
@@ -0,0 +1,124 @@
+//! Battle log resources
+
+use crate::game::simulation::*;
+
+/// Gets the display name for a side
+fn side_name(player: bool) -> &'static str {
+    if player {
+        "Player"
+    } else {
+        "AI"
+    }
+}
+
+/// A single battle log entry
+#[derive(Debug, Clone)]
+pub struct BattleLogEntry {
+    /// The turn the event happened on
+    pub turn: usize,
+
+    pub event: SimulationEvent,
+}
+
+impl BattleLogEntry {
+    /// Gets a human readable description of the entry
+    pub fn description(&self) -> String {
+        let description = match &self.event {
+            SimulationEvent::Moved {
+                player,
+                from,
+                to,
+                kind: MoveKind::Brain { output },
+                ..
+            } => format!(
+                "{} moved {} -> {} (Brain, output {:.2})",
+                side_name(*player),
+                from,
+                to,
+                output
+            ),
+            SimulationEvent::Moved {
+                player,
+                from,
+                to,
+                kind,
+                enemy_roll,
+                food_roll,
+            } => {
+                let enemy_roll = match enemy_roll {
+                    Some(roll) => format!(", enemy roll {:.2}", roll),
+                    None => String::new(),
+                };
+                let food_roll = match food_roll {
+                    Some(roll) => format!(", food roll {:.2}", roll),
+                    None => String::new(),
+                };
+
+                format!(
+                    "{} moved {} -> {} ({:?}{}{})",
+                    side_name(*player),
+                    from,
+                    to,
+                    kind,
+                    enemy_roll,
+                    food_roll
+                )
+            }
+            SimulationEvent::Attacked {
+                player,
+                damage,
+                absorbed,
+            } => format!(
+                "{} attacked for {} damage ({} absorbed)",
+                side_name(*player),
+                damage,
+                absorbed
+            ),
+            SimulationEvent::AteFood {
+                player,
+                cell,
+                health,
+            } => format!(
+                "{} ate food at {}, health {}",
+                side_name(*player),
+                cell,
+                health
+            ),
+            SimulationEvent::FoodSpawned { cell } => format!("Food spawned at {}", cell),
+            SimulationEvent::Died { player } => format!("{} died", side_name(*player)),
+        };
+
+        format!("{}: {}", self.turn + 1, description)
+    }
+}
+
+/// Per-turn journal of everything that happened in a match
+#[derive(Debug, Default)]
+pub struct BattleLog {
+    entries: Vec<BattleLogEntry>,
+}
+
+impl BattleLog {
+    /// Gets the log entries
+    #[inline]
+    pub fn entries(&self) -> &[BattleLogEntry] {
+        &self.entries
+    }
+
+    /// Records the events from a turn
+    ///
+    /// Food spawns are skipped, they're just noise
+    pub fn record(&mut self, turn: usize, events: Vec<SimulationEvent>) {
+        self.entries.extend(
+            events
+                .into_iter()
+                .filter(|event| !matches!(event, SimulationEvent::FoodSpawned { .. }))
+                .map(|event| BattleLogEntry { turn, event }),
+        );
+    }
+
+    /// Clears the log
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+}
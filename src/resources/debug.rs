@@ -4,4 +4,7 @@
 #[derive(Debug, Default)]
 pub struct DebugState {
     pub enabled: bool,
+
+    /// Show the evolution window
+    pub evolution: bool,
 }
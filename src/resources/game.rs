@@ -1,9 +1,14 @@
 //! Game resources
 
 use derivative::*;
+use serde::{Deserialize, Serialize};
+
+use crate::game::persist::*;
+use crate::game::simulation::*;
+use crate::game::stats::*;
 
 /// The game stages
-#[derive(Debug, Clone, Copy, Eq, PartialEq, Derivative)]
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Derivative, Serialize, Deserialize)]
 #[derivative(Default)]
 pub enum GameStage {
     /// The player is selecting their spawn cell
@@ -12,35 +17,172 @@ pub enum GameStage {
 
     /// The simulation is running
     Running,
+
+    /// The simulation is finished
+    Finished,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct GameRound {
+    pub round: usize,
+    pub stage: GameStage,
+
+    /// The winner of each completed round, None for a draw
+    pub winners: Vec<Option<RoundWinner>>,
+
+    /// The AI automata stats of each started round
+    #[serde(default)]
+    pub opponents: Vec<StatSet>,
+
+    /// Number of rounds in the game
+    #[serde(default)]
+    pub rounds: usize,
+}
+
+impl Persist for GameRound {}
+
+impl GameRound {
+    /// Creates a new game with the given number of rounds
+    pub fn new(rounds: usize) -> Self {
+        Self {
+            rounds,
+            ..Default::default()
+        }
+    }
+
+    pub fn reset(&mut self) {
+        self.stage = GameStage::default();
+    }
+
+    /// Records the round winner and advances to the next round
+    ///
+    /// Returns true if there are rounds remaining
+    pub fn advance(&mut self, winner: Option<RoundWinner>) -> bool {
+        self.winners.push(winner);
+        self.round += 1;
+
+        self.round < self.rounds
+    }
+
+    /// Gets the number of rounds won by the player
+    pub fn player_wins(&self) -> usize {
+        self.winners
+            .iter()
+            .filter(|winner| **winner == Some(RoundWinner::Player))
+            .count()
+    }
 }
 
+/// Number of turns run per frame at instant speed
+const INSTANT_TURNS: usize = 1000;
+
+/// Simulation speed multipliers
 #[derive(Debug, Clone, Copy, Eq, PartialEq, Derivative)]
 #[derivative(Default)]
-pub enum GameAction {
-    /// The player's move action
+pub enum SimulationSpeed {
+    /// Half speed
+    Half,
+
+    /// Normal speed
     #[derivative(Default)]
-    PlayerMove,
+    Normal,
+
+    /// Quadruple speed
+    Quadruple,
 
-    /// The player's attack action
-    PlayerAttack,
+    /// Run as many turns as possible each frame
+    Instant,
+}
+
+impl SimulationSpeed {
+    /// All of the simulation speeds, slowest first
+    pub const ALL: [SimulationSpeed; 4] = [
+        SimulationSpeed::Half,
+        SimulationSpeed::Normal,
+        SimulationSpeed::Quadruple,
+        SimulationSpeed::Instant,
+    ];
 
-    /// The AI's move action
-    AIMove,
+    pub fn name(&self) -> &'static str {
+        match self {
+            SimulationSpeed::Half => "0.5x",
+            SimulationSpeed::Normal => "1x",
+            SimulationSpeed::Quadruple => "4x",
+            SimulationSpeed::Instant => "Instant",
+        }
+    }
 
-    /// The AI's attack action
-    AIAttack,
+    /// Gets the time multiplier, if the speed has one
+    pub fn multiplier(&self) -> Option<f32> {
+        match self {
+            SimulationSpeed::Half => Some(0.5),
+            SimulationSpeed::Normal => Some(1.0),
+            SimulationSpeed::Quadruple => Some(4.0),
+            SimulationSpeed::Instant => None,
+        }
+    }
 }
 
-#[derive(Debug, Default)]
-pub struct GameRound {
-    pub round: usize,
-    pub stage: GameStage,
-    pub action: GameAction,
+/// Fixed timestep simulation clock
+#[derive(Debug)]
+pub struct SimulationClock {
+    /// Seconds between turns at normal speed
+    pub interval: f32,
+
+    pub speed: SimulationSpeed,
+    pub paused: bool,
+
+    accumulator: f32,
+    step: bool,
 }
 
-impl GameRound {
+impl SimulationClock {
+    /// Creates a new simulation clock
+    pub fn new(interval: f32) -> Self {
+        Self {
+            interval,
+            speed: SimulationSpeed::default(),
+            paused: false,
+            accumulator: 0.0,
+            step: false,
+        }
+    }
+
+    /// Resets any accumulated time
     pub fn reset(&mut self) {
-        self.stage = GameStage::default();
-        self.action = GameAction::default();
+        self.accumulator = 0.0;
+        self.step = false;
+    }
+
+    /// Requests a single turn while paused
+    pub fn step(&mut self) {
+        self.step = true;
+    }
+
+    /// Advances the clock by the frame time
+    ///
+    /// Returns the number of turns that should be run
+    pub fn tick(&mut self, dt: f32) -> usize {
+        if self.paused {
+            self.accumulator = 0.0;
+            if self.step {
+                self.step = false;
+                return 1;
+            }
+            return 0;
+        }
+        self.step = false;
+
+        let multiplier = match self.speed.multiplier() {
+            Some(multiplier) => multiplier,
+            None => return INSTANT_TURNS,
+        };
+
+        self.accumulator += dt * multiplier;
+
+        let turns = (self.accumulator / self.interval) as usize;
+        self.accumulator -= turns as f32 * self.interval;
+
+        turns
     }
 }
@@ -1,9 +1,11 @@
 //! ECS resources
 
 pub mod automata;
+pub mod balance;
+pub mod battlelog;
 pub mod debug;
 pub mod game;
-pub mod gridworld;
+pub mod replay;
 pub mod ui;
 
 use bevy::prelude::*;
@@ -16,16 +18,16 @@ use crate::util::clampf;
 
 /// Random wrapper
 pub struct Random {
+    seed: u64,
+
     // TODO: would SmallRng be better here? we don't need a secure rng
     random: StdRng,
 }
 
 impl Default for Random {
-    /// Constructs a default random from system entropy
+    /// Constructs a default random from a system entropy seed
     fn default() -> Self {
-        Self {
-            random: StdRng::from_entropy(),
-        }
+        Self::new(StdRng::from_entropy().gen())
     }
 }
 
@@ -34,10 +36,17 @@ impl Random {
     #[allow(dead_code)]
     pub fn new(seed: u64) -> Self {
         Self {
+            seed,
             random: StdRng::seed_from_u64(seed),
         }
     }
 
+    /// Gets the seed this random was constructed from
+    #[allow(dead_code)]
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
     #[allow(dead_code)]
     pub fn shuffle<T>(&mut self, v: &mut Vec<T>) {
         v.shuffle(&mut self.random);
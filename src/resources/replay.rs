@@ -0,0 +1,35 @@
+//! Replay resources
+
+use crate::game::replay::*;
+
+/// Replay playback state
+#[derive(Debug)]
+pub struct ReplayPlayback {
+    /// The played back state after each turn
+    pub frames: Vec<ReplayFrame>,
+
+    /// The current frame
+    pub frame: usize,
+}
+
+impl ReplayPlayback {
+    /// Creates a new playback of a replay
+    pub fn new(replay: &Replay) -> Self {
+        Self {
+            frames: replay.frames(),
+            frame: 0,
+        }
+    }
+
+    /// Gets the current frame
+    #[inline]
+    pub fn current(&self) -> &ReplayFrame {
+        &self.frames[self.frame]
+    }
+
+    /// Gets the last frame index
+    #[inline]
+    pub fn last(&self) -> usize {
+        self.frames.len() - 1
+    }
+}
@@ -1,7 +1,10 @@
 //! Game state systems
 
+use std::path::Path;
+
 use bevy::prelude::*;
 use bevy::render::camera::ScalingMode;
+use bevy_egui::{egui, EguiContext};
 
 use super::*;
 
@@ -12,9 +15,13 @@ use crate::components::gridworld::*;
 use crate::components::ui::*;
 use crate::components::*;
 use crate::events::game::*;
+use crate::game::balance::*;
+use crate::game::gridworld::*;
+use crate::game::persist::*;
+use crate::game::simulation::*;
 use crate::resources::automata::*;
+use crate::resources::battlelog::*;
 use crate::resources::game::*;
-use crate::resources::gridworld::*;
 use crate::resources::ui::*;
 use crate::util::*;
 use crate::*;
@@ -61,15 +68,8 @@ fn spawn_cell_selection_row(parent: &mut ChildBuilder, button_colors: &ButtonCol
         });
 }
 
-/// Game setup
-pub fn setup(
-    mut commands: Commands,
-    gridworld: Res<GridWorld>,
-    mut round: ResMut<GameRound>,
-    colors: Res<AutomataColors>,
-    button_colors: Res<ButtonColors>,
-    fonts: Res<Fonts>,
-) {
+/// Spawn the main camera and the GridWorld cells
+pub(super) fn spawn_world(commands: &mut Commands, gridworld: &GridWorld, colors: &AutomataColors) {
     // cameras
     let mut camera = Camera2dBundle::default();
     camera.projection.scaling_mode = ScalingMode::FixedVertical(GRID_HEIGHT as f32 / 2.0);
@@ -87,53 +87,20 @@ pub fn setup(
         .insert(Name::new("GridWorld"))
         .id();
     for cell in gridworld.cells.iter() {
-        GridWorldCell::spawn(&mut commands, parent, cell.0, colors.cell);
+        GridWorldCell::spawn(commands, parent, cell.0, colors.cell);
     }
+}
 
-    round.reset();
-
-    // cell selection UI
-    let root = spawn_ui_root(&mut commands);
-    commands
-        .entity(root)
-        .insert(CellSelection)
-        .with_children(|parent| {
-            parent
-                .spawn_bundle(NodeBundle {
-                    style: Style {
-                        size: Size::new(Val::Auto, Val::Auto),
-                        align_items: AlignItems::FlexStart,
-                        ..Default::default()
-                    },
-                    color: Color::NONE.into(),
-                    ..Default::default()
-                })
-                .insert(Name::new("Cell Selection"))
-                .with_children(|parent| {
-                    parent.spawn_bundle(TextBundle {
-                        style: Style {
-                            margin: UiRect::all(Val::Px(5.0)),
-                            ..Default::default()
-                        },
-                        text: Text::from_section(
-                            "Select a cell for your automaton",
-                            TextStyle {
-                                font: fonts.normal.clone(),
-                                font_size: 30.0,
-                                color: Color::WHITE,
-                            },
-                        ),
-                        ..Default::default()
-                    });
-                });
-
-            for row in 0..GRID_HEIGHT {
-                spawn_cell_selection_row(parent, &button_colors, row);
-            }
-        });
-
-    // HUD UI
-    let root = spawn_ui_root(&mut commands);
+/// Spawn the round HUD
+pub(super) fn spawn_hud(
+    commands: &mut Commands,
+    fonts: &Fonts,
+    round: usize,
+    player_health: usize,
+    ai_health: usize,
+    visible: bool,
+) {
+    let root = spawn_ui_root(commands);
     commands.entity(root).insert(Hud).with_children(|parent| {
         parent
             .spawn_bundle(NodeBundle {
@@ -143,7 +110,9 @@ pub fn setup(
                     ..Default::default()
                 },
                 color: Color::NONE.into(),
-                visibility: Visibility { is_visible: false },
+                visibility: Visibility {
+                    is_visible: visible,
+                },
                 ..Default::default()
             })
             .insert(Name::new("HUD"))
@@ -161,7 +130,9 @@ pub fn setup(
                             color: Color::WHITE,
                         },
                     ),
-                    visibility: Visibility { is_visible: false },
+                    visibility: Visibility {
+                        is_visible: visible,
+                    },
                     ..Default::default()
                 });
 
@@ -172,14 +143,16 @@ pub fn setup(
                             ..Default::default()
                         },
                         text: Text::from_section(
-                            format!("{}", 0),
+                            format!("{}", player_health),
                             TextStyle {
                                 font: fonts.normal.clone(),
                                 font_size: 30.0,
                                 color: Color::WHITE,
                             },
                         ),
-                        visibility: Visibility { is_visible: false },
+                        visibility: Visibility {
+                            is_visible: visible,
+                        },
                         ..Default::default()
                     },
                     health_text: AutomataHealthText { player: true },
@@ -201,7 +174,9 @@ pub fn setup(
                             color: Color::WHITE,
                         },
                     ),
-                    visibility: Visibility { is_visible: false },
+                    visibility: Visibility {
+                        is_visible: visible,
+                    },
                     ..Default::default()
                 });
 
@@ -212,14 +187,16 @@ pub fn setup(
                             ..Default::default()
                         },
                         text: Text::from_section(
-                            format!("{}", round.round + 1),
+                            format!("{}", round + 1),
                             TextStyle {
                                 font: fonts.normal.clone(),
                                 font_size: 30.0,
                                 color: Color::WHITE,
                             },
                         ),
-                        visibility: Visibility { is_visible: false },
+                        visibility: Visibility {
+                            is_visible: visible,
+                        },
                         ..Default::default()
                     },
                     round_text: RoundText,
@@ -241,7 +218,9 @@ pub fn setup(
                             color: Color::WHITE,
                         },
                     ),
-                    visibility: Visibility { is_visible: false },
+                    visibility: Visibility {
+                        is_visible: visible,
+                    },
                     ..Default::default()
                 });
 
@@ -252,14 +231,16 @@ pub fn setup(
                             ..Default::default()
                         },
                         text: Text::from_section(
-                            format!("{}", 0),
+                            format!("{}", ai_health),
                             TextStyle {
                                 font: fonts.normal.clone(),
                                 font_size: 30.0,
                                 color: Color::WHITE,
                             },
                         ),
-                        visibility: Visibility { is_visible: false },
+                        visibility: Visibility {
+                            is_visible: visible,
+                        },
                         ..Default::default()
                     },
                     health_text: AutomataHealthText { player: false },
@@ -268,19 +249,79 @@ pub fn setup(
     });
 }
 
+/// Game setup
+pub fn setup(
+    mut commands: Commands,
+    gridworld: Res<GridWorld>,
+    mut round: ResMut<GameRound>,
+    colors: Res<AutomataColors>,
+    button_colors: Res<ButtonColors>,
+    fonts: Res<Fonts>,
+) {
+    spawn_world(&mut commands, &gridworld, &colors);
+
+    round.reset();
+
+    // cell selection UI
+    let root = spawn_ui_root(&mut commands);
+    commands
+        .entity(root)
+        .insert(CellSelection)
+        .with_children(|parent| {
+            parent
+                .spawn_bundle(NodeBundle {
+                    style: Style {
+                        size: Size::new(Val::Auto, Val::Auto),
+                        align_items: AlignItems::FlexStart,
+                        ..Default::default()
+                    },
+                    color: Color::NONE.into(),
+                    ..Default::default()
+                })
+                .insert(Name::new("Cell Selection"))
+                .with_children(|parent| {
+                    parent.spawn_bundle(TextBundle {
+                        style: Style {
+                            margin: UiRect::all(Val::Px(5.0)),
+                            ..Default::default()
+                        },
+                        text: Text::from_section(
+                            "Select a cell for your automaton",
+                            TextStyle {
+                                font: fonts.normal.clone(),
+                                font_size: 30.0,
+                                color: Color::WHITE,
+                            },
+                        ),
+                        ..Default::default()
+                    });
+                });
+
+            for row in 0..GRID_HEIGHT {
+                spawn_cell_selection_row(parent, &button_colors, row);
+            }
+        });
+
+    // HUD UI
+    spawn_hud(&mut commands, &fonts, round.round, 0, 0, false);
+}
+
 /// Cell selection button handler
 pub fn cell_selection_button_handler(
     mut commands: Commands,
     mut random: ResMut<Random>,
     mut round: ResMut<GameRound>,
+    balance: Res<Balance>,
     colors: Res<AutomataColors>,
+    player_stats: Res<PlayerAutomataStats>,
+    ai_population: Res<AIAutomataPopulation>,
     query: Query<(&Interaction, &ButtonHelper, &CellSelectionButton), Changed<Interaction>>,
     cell_selection_ui_query: Query<Entity, With<CellSelection>>,
     hud_query: Query<Entity, With<Hud>>,
     mut visibility_query: Query<&mut Visibility>,
     children_query: Query<&Children>,
-
     mut game_start_events: EventWriter<GameStartEvent>,
+    mut health_changed_events: EventWriter<HealthChangedEvent>,
 ) {
     if round.stage != GameStage::CellSelection {
         return;
@@ -303,21 +344,45 @@ pub fn cell_selection_button_handler(
             debug!("Enabling HUD...");
             set_visible_recursive(hud, true, &mut visibility_query, &children_query);
 
+            // start the simulation
+            let player_cell = UVec2::new(selection.cell.x as u32, selection.cell.y as u32);
+            let opponent = ai_population.round_opponent(&round.opponents);
+            let simulation = Simulation::with_player_cell(
+                *player_stats.stats(),
+                *opponent.genes(),
+                crate::GRID_WIDTH,
+                crate::GRID_HEIGHT,
+                player_cell,
+                *balance,
+                Random::new(random.random_range(0..u64::MAX)),
+            )
+            .with_ai_brain(opponent.brain().cloned());
+            round.opponents.push(simulation.ai().stats);
+
             // spawn automata
             let parent = commands
                 .spawn_bundle(EmptyBundle::default())
                 .insert(Name::new("Automata"))
                 .id();
 
-            let player_cell = UVec2::new(selection.cell.x as u32, selection.cell.y as u32);
-            Automata::spawn_player(&mut commands, parent, colors.player_automata, player_cell);
-            Automata::spawn_ai(
+            Automata::spawn_player(
                 &mut commands,
                 parent,
-                colors.ai_automata,
-                player_cell,
-                &mut random,
+                colors.player_automata,
+                simulation.player(),
             );
+            Automata::spawn_ai(&mut commands, parent, colors.ai_automata, simulation.ai());
+
+            health_changed_events.send(HealthChangedEvent {
+                player: true,
+                value: simulation.player().health as isize,
+            });
+            health_changed_events.send(HealthChangedEvent {
+                player: false,
+                value: simulation.ai().health as isize,
+            });
+
+            commands.insert_resource(simulation);
 
             game_start_events.send(GameStartEvent);
 
@@ -329,38 +394,10 @@ pub fn cell_selection_button_handler(
 /// Game start event handler
 pub fn game_start_event_handler(
     mut events: EventReader<GameStartEvent>,
-    round: Res<GameRound>,
-    player_stats: Res<PlayerAutomataStats>,
-    ai_population: Res<AIAutomataPopulation>,
-    mut player_automata_query: Query<&mut Automata, (With<PlayerAutomata>, Without<AIAutomata>)>,
-    mut ai_automata_query: Query<&mut Automata, (With<AIAutomata>, Without<PlayerAutomata>)>,
-    mut health_text_query: Query<(&mut Text, &AutomataHealthText)>,
+    mut clock: ResMut<SimulationClock>,
 ) {
     for _ in events.iter() {
-        if let Ok(mut automata) = player_automata_query.get_single_mut() {
-            automata.reset(&*player_stats);
-
-            for (mut text, health) in health_text_query.iter_mut() {
-                if !health.player {
-                    continue;
-                }
-
-                text.sections[0].value = format!("{}", automata.health);
-            }
-        }
-
-        if let Ok(mut automata) = ai_automata_query.get_single_mut() {
-            let ai_stats = ai_population.round_stats(round.round);
-            automata.reset(ai_stats);
-
-            for (mut text, health) in health_text_query.iter_mut() {
-                if health.player {
-                    continue;
-                }
-
-                text.sections[0].value = format!("{}", automata.health);
-            }
-        }
+        clock.reset();
     }
 }
 
@@ -379,52 +416,241 @@ pub fn health_changed_event_handler(
 }
 
 /// Automata action handler
-#[allow(clippy::needless_return)]
+///
+/// Runs a simulation turn for every turn the simulation clock advances
+/// and updates the automata and food to match the simulation state
 pub fn automata_action(
-    mut round: ResMut<GameRound>,
-    mut player_automata_query: Query<&mut Automata, (With<PlayerAutomata>, Without<AIAutomata>)>,
-    mut ai_automata_query: Query<&mut Automata, (With<AIAutomata>, Without<PlayerAutomata>)>,
+    mut commands: Commands,
+    time: Res<Time>,
+    mut clock: ResMut<SimulationClock>,
+    round: Res<GameRound>,
+    simulation: Option<ResMut<Simulation>>,
+    mut battle_log: ResMut<BattleLog>,
+    colors: Res<AutomataColors>,
+    mut player_automata_query: Query<
+        (&mut Automata, &mut Transform),
+        (With<PlayerAutomata>, Without<AIAutomata>),
+    >,
+    mut ai_automata_query: Query<
+        (&mut Automata, &mut Transform),
+        (With<AIAutomata>, Without<PlayerAutomata>),
+    >,
+    food_query: Query<(Entity, &Food)>,
+    mut health_changed_events: EventWriter<HealthChangedEvent>,
 ) {
     if round.stage != GameStage::Running {
         return;
     }
 
-    // TODO: we need a cooldown on taking actions
-    // so things don't progress too fast
+    let mut simulation = match simulation {
+        Some(simulation) => simulation,
+        None => return,
+    };
 
-    if let Ok(mut player) = player_automata_query.get_single_mut() {
-        if let Ok(mut ai) = ai_automata_query.get_single_mut() {
-            match round.action {
-                GameAction::PlayerMove => {
-                    player.move_action();
+    for _ in 0..clock.tick(time.delta_seconds()) {
+        if simulation.is_finished() {
+            break;
+        }
 
-                    round.action = GameAction::PlayerAttack;
-                }
-                GameAction::PlayerAttack => {
-                    player.attack_action();
+        let turn = simulation.turn();
+        let events = simulation.step();
+        battle_log.record(turn, events);
+    }
 
-                    round.action = GameAction::AIMove;
-                }
-                GameAction::AIMove => {
-                    ai.move_action();
+    sync_world(
+        &mut commands,
+        simulation.player(),
+        simulation.ai(),
+        simulation.food(),
+        &colors,
+        &mut player_automata_query,
+        &mut ai_automata_query,
+        &food_query,
+        &mut health_changed_events,
+    );
+}
 
-                    round.action = GameAction::AIAttack;
-                }
-                GameAction::AIAttack => {
-                    ai.attack_action();
+/// Updates the automata and food entities to match the simulation state
+pub(super) fn sync_world(
+    commands: &mut Commands,
+    player: &Combatant,
+    ai: &Combatant,
+    food: &[UVec2],
+    colors: &AutomataColors,
+    player_automata_query: &mut Query<
+        (&mut Automata, &mut Transform),
+        (With<PlayerAutomata>, Without<AIAutomata>),
+    >,
+    ai_automata_query: &mut Query<
+        (&mut Automata, &mut Transform),
+        (With<AIAutomata>, Without<PlayerAutomata>),
+    >,
+    food_query: &Query<(Entity, &Food)>,
+    health_changed_events: &mut EventWriter<HealthChangedEvent>,
+) {
+    // automata
+    if let Ok((mut automata, mut transform)) = player_automata_query.get_single_mut() {
+        if automata.sync(&mut transform, player) {
+            health_changed_events.send(HealthChangedEvent {
+                player: true,
+                value: automata.health as isize,
+            });
+        }
+    }
 
-                    round.action = GameAction::PlayerMove;
-                }
-            };
+    if let Ok((mut automata, mut transform)) = ai_automata_query.get_single_mut() {
+        if automata.sync(&mut transform, ai) {
+            health_changed_events.send(HealthChangedEvent {
+                player: false,
+                value: automata.health as isize,
+            });
         }
     }
+
+    // food
+    Food::sync(commands, food_query, food, colors.food);
+}
+
+/// Simulation controls UI
+pub fn simulation_controls_ui(
+    round: Res<GameRound>,
+    mut clock: ResMut<SimulationClock>,
+    mut context: ResMut<EguiContext>,
+) {
+    if round.stage != GameStage::Running {
+        return;
+    }
+
+    egui::Window::new("Simulation")
+        .anchor(egui::Align2::CENTER_BOTTOM, [0.0, -5.0])
+        .collapsible(false)
+        .resizable(false)
+        .show(context.ctx_mut(), |ui| {
+            ui.horizontal(|ui| {
+                let paused = clock_controls(ui, &mut clock);
+
+                if ui.add_enabled(paused, egui::Button::new("Step")).clicked() {
+                    clock.step();
+                }
+            });
+        });
+}
+
+/// Adds the simulation speed and pause controls
+///
+/// Returns true if the clock was paused before the controls were shown
+pub(super) fn clock_controls(ui: &mut egui::Ui, clock: &mut SimulationClock) -> bool {
+    for speed in SimulationSpeed::ALL {
+        ui.selectable_value(&mut clock.speed, speed, speed.name());
+    }
+
+    ui.separator();
+
+    let paused = clock.paused;
+    if ui.button(if paused { "Play" } else { "Pause" }).clicked() {
+        clock.paused = !paused;
+    }
+
+    paused
+}
+
+/// Battle log UI
+pub fn battle_log_ui(
+    round: Res<GameRound>,
+    battle_log: Res<BattleLog>,
+    mut context: ResMut<EguiContext>,
+) {
+    if round.stage == GameStage::CellSelection {
+        return;
+    }
+
+    egui::SidePanel::right("battle_log")
+        .default_width(250.0)
+        .show(context.ctx_mut(), |ui| {
+            ui.heading("Battle Log");
+            ui.separator();
+
+            egui::ScrollArea::vertical()
+                .stick_to_bottom()
+                .show(ui, |ui| {
+                    for entry in battle_log.entries() {
+                        ui.label(entry.description());
+                    }
+                });
+        });
+}
+
+/// Round end handler
+///
+/// Ends the round once the simulation is finished
+pub fn round_end(
+    mut random: ResMut<Random>,
+    balance: Res<Balance>,
+    mut round: ResMut<GameRound>,
+    player_stats: Res<PlayerAutomataStats>,
+    mut ai_population: ResMut<AIAutomataPopulation>,
+    simulation: Option<Res<Simulation>>,
+    mut state: ResMut<State<GameState>>,
+) {
+    if round.stage != GameStage::Running {
+        return;
+    }
+
+    let simulation = match simulation {
+        Some(simulation) => simulation,
+        None => return,
+    };
+
+    if !simulation.is_finished() {
+        return;
+    }
+
+    let result = simulation.result();
+    info!(
+        "Round {} winner: {:?} after {} turns",
+        round.round + 1,
+        result.winner,
+        result.turns
+    );
+
+    let mut replay = simulation.replay().clone();
+    replay.round = round.round;
+
+    let path =
+        Path::new(crate::REPLAY_DIR).join(format!("round-{}-{}.ron", round.round + 1, replay.seed));
+    match replay.save(&path) {
+        Ok(_) => info!("Saved replay to {}", path.display()),
+        Err(err) => error!("Failed to save replay to {}: {}", path.display(), err),
+    }
+
+    round.stage = GameStage::Finished;
+    ai_population.record_player(*player_stats.stats());
+
+    // evolve the AI against the player builds after every round,
+    // the last round breeds the population the next run starts with
+    ai_population.evaluate(crate::GRID_WIDTH, crate::GRID_HEIGHT, &balance, &mut random);
+    ai_population.next_generation(&mut random);
+
+    if round.advance(result.winner) {
+        state.set(GameState::Remix).unwrap();
+    } else {
+        state.set(GameState::GameOver).unwrap();
+    }
 }
 
 /// Game teardown
-pub fn teardown(mut commands: Commands, entities: Query<Entity>) {
+pub fn teardown(
+    mut commands: Commands,
+    mut battle_log: ResMut<BattleLog>,
+    entities: Query<Entity>,
+) {
     for entity in entities.iter() {
         commands.entity(entity).despawn_recursive();
     }
 
+    battle_log.clear();
+
+    commands.remove_resource::<Simulation>();
+
     commands.remove_resource::<ClearColor>();
 }
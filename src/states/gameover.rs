@@ -6,12 +6,40 @@ use super::*;
 
 use crate::components::ui::*;
 use crate::components::*;
+use crate::game::persist::*;
+use crate::options::*;
 use crate::resources::automata::*;
 use crate::resources::game::*;
 use crate::resources::ui::*;
+use crate::resources::*;
 
 /// Game over setup
-pub fn setup(mut commands: Commands, button_colors: Res<ButtonColors>, fonts: Res<Fonts>) {
+pub fn setup(
+    mut commands: Commands,
+    round: Res<GameRound>,
+    random: Res<Random>,
+    options: Res<Options>,
+    ai_population: Res<AIAutomataPopulation>,
+    button_colors: Res<ButtonColors>,
+    fonts: Res<Fonts>,
+) {
+    // keep the evolved population and player history for the next run,
+    // an imported population file is left as it was exported
+    if options.persistent() {
+        match ai_population.save(crate::POPULATION_PATH) {
+            Ok(_) => info!(
+                "Saved AI population (generation {}) to {}",
+                ai_population.generation(),
+                crate::POPULATION_PATH
+            ),
+            Err(err) => error!(
+                "Failed to save AI population to {}: {}",
+                crate::POPULATION_PATH,
+                err
+            ),
+        }
+    }
+
     // cameras
     commands.insert_resource(ClearColor(Color::rgb(0.0, 0.0, 0.0)));
     commands
@@ -24,6 +52,18 @@ pub fn setup(mut commands: Commands, button_colors: Res<ButtonColors>, fonts: Re
     commands.entity(root).with_children(|parent| {
         spawn_header(parent, &fonts, "Game Over");
 
+        spawn_header(
+            parent,
+            &fonts,
+            format!(
+                "Rounds won: {} / {}",
+                round.player_wins(),
+                round.winners.len()
+            ),
+        );
+
+        spawn_header(parent, &fonts, format!("Seed: {}", random.seed()));
+
         spawn_spacer(parent);
 
         spawn_ok_action(parent, &button_colors, &fonts, "Continue", true);
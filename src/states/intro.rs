@@ -6,10 +6,12 @@ use super::*;
 
 use crate::components::ui::*;
 use crate::components::*;
-use crate::game::dna::MUTATION_RATE;
+use crate::game::balance::*;
+use crate::game::gridworld::*;
+use crate::game::persist::*;
+use crate::options::*;
 use crate::resources::automata::*;
 use crate::resources::game::*;
-use crate::resources::gridworld::*;
 use crate::resources::ui::*;
 use crate::resources::*;
 
@@ -17,6 +19,8 @@ use crate::resources::*;
 pub fn setup(
     mut commands: Commands,
     mut random: ResMut<Random>,
+    options: Res<Options>,
+    balance: Res<Balance>,
     button_colors: Res<ButtonColors>,
     fonts: Res<Fonts>,
 ) {
@@ -32,26 +36,62 @@ pub fn setup(
     commands.insert_resource(gridworld);
 
     // player automata stats
-    let player_stats = PlayerAutomataStats::new(crate::STAT_POINTS);
+    let player_stats = PlayerAutomataStats::new(balance.stat_points);
     commands.insert_resource(player_stats);
 
-    // AI automata population
-    let ai_population = AIAutomataPopulation::new(
-        MUTATION_RATE,
-        crate::ROUNDS,
-        crate::STAT_POINTS,
-        &mut random,
-    );
+    // AI automata population, from the last run or training if there is one
+    if !options.persistent() {
+        info!("Seeded run, ignoring the saved AI population");
+    }
+
+    let population_path = options
+        .game_population_path()
+        .filter(|population_path| population_path.exists());
+    let saved_population = if let Some(population_path) = population_path {
+        match AIAutomataPopulation::load(&population_path) {
+            Ok(mut ai_population) => {
+                ai_population.apply_balance(&balance);
+                info!(
+                    "Loaded AI population (generation {}) from {}",
+                    ai_population.generation(),
+                    population_path.display()
+                );
+                Some(ai_population)
+            }
+            Err(err) => {
+                warn!(
+                    "Failed to load AI population from {}: {}",
+                    population_path.display(),
+                    err
+                );
+                None
+            }
+        }
+    } else {
+        None
+    };
+
+    let ai_population = saved_population.unwrap_or_else(|| {
+        AIAutomataPopulation::new(
+            options.population_config(&balance),
+            balance.rounds,
+            balance.stat_points,
+            &mut random,
+        )
+    });
+
     commands.insert_resource(ai_population);
 
     // round
-    commands.insert_resource(GameRound::default());
+    commands.insert_resource(GameRound::new(balance.rounds));
 
     // UI
     let root = spawn_ui_root(&mut commands);
     commands.entity(root).with_children(|parent| {
         spawn_header(parent, &fonts, "Remix Exploration");
 
+        spawn_header(parent, &fonts, format!("Seed: {}", random.seed()));
+
         spawn_spacer(parent);
 
         spawn_ok_action(parent, &button_colors, &fonts, "Play", true);
@@ -0,0 +1,41 @@
+//! Loading state systems
+
+use bevy::asset::LoadState;
+use bevy::prelude::*;
+
+use super::*;
+
+use crate::game::balance::*;
+use crate::game::replay::*;
+use crate::resources::balance::*;
+
+/// Waits for the balance asset, then starts the game or the replay
+pub fn balance_loaded(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    assets: Res<Assets<Balance>>,
+    balance_asset: Res<BalanceAsset>,
+    replay: Option<Res<Replay>>,
+    mut state: ResMut<State<GameState>>,
+) {
+    let balance = match asset_server.get_load_state(&balance_asset.handle) {
+        LoadState::Loaded => match assets.get(&balance_asset.handle) {
+            Some(balance) => *balance,
+            None => return,
+        },
+        LoadState::Failed => {
+            warn!("Failed to load the balance asset, using the default balance");
+            Balance::default()
+        }
+        _ => return,
+    };
+
+    info!("Applying balance: {:?}", balance);
+    commands.insert_resource(balance);
+
+    if replay.is_some() {
+        state.set(GameState::Replay).unwrap();
+    } else {
+        state.set(GameState::Intro).unwrap();
+    }
+}
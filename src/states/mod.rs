@@ -3,7 +3,9 @@
 pub mod game;
 pub mod gameover;
 pub mod intro;
+pub mod loading;
 pub mod remix;
+pub mod replay;
 
 use bevy::prelude::*;
 
@@ -14,6 +16,9 @@ use crate::resources::ui::*;
 /// The game state
 #[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
 pub enum GameState {
+    /// Loading state - Wait for the game assets
+    Loading,
+
     /// Intro state - Explain how to play the game
     Intro,
 
@@ -25,6 +30,9 @@ pub enum GameState {
 
     /// Game over state - All rounds complete, show results
     GameOver,
+
+    /// Replay state - Play back a recorded match
+    Replay,
 }
 
 // TODO: move these UI helpers somewhere else
@@ -19,7 +19,6 @@ fn spawn_stat_input(
     fonts: &Fonts,
     statid: StatId,
     player_stats: &PlayerAutomataStats,
-    description: impl Into<String>,
 ) {
     parent
         .spawn_bundle(NodeBundle {
@@ -91,10 +90,14 @@ fn spawn_stat_input(
                                     align_items: AlignItems::Center,
                                     ..Default::default()
                                 },
-                                color: Color::NONE.into(),
+                                color: if player_stats.points() > 0 {
+                                    button_colors.normal
+                                } else {
+                                    button_colors.disabled
+                                },
                                 ..Default::default()
                             },
-                            helper: ButtonHelper::new(true),
+                            helper: ButtonHelper::new(player_stats.points() > 0),
                             modifier_button: StatModifierButton {
                                 statid,
                                 modifier: 1,
@@ -124,10 +127,14 @@ fn spawn_stat_input(
                                     align_items: AlignItems::Center,
                                     ..Default::default()
                                 },
-                                color: button_colors.disabled,
+                                color: if player_stats.value(statid) > 0 {
+                                    button_colors.normal
+                                } else {
+                                    button_colors.disabled
+                                },
                                 ..Default::default()
                             },
-                            helper: ButtonHelper::new(false),
+                            helper: ButtonHelper::new(player_stats.value(statid) > 0),
                             modifier_button: StatModifierButton {
                                 statid,
                                 modifier: -1,
@@ -154,7 +161,7 @@ fn spawn_stat_input(
                     ..Default::default()
                 },
                 text: Text::from_section(
-                    description,
+                    statid.description(),
                     TextStyle {
                         font: fonts.normal.clone(),
                         font_size: 14.0,
@@ -234,63 +241,19 @@ pub fn setup(
                 });
             });
 
-        spawn_stat_input(
-            parent,
-            &button_colors,
-            &fonts,
-            StatId::Constitution,
-            &player_stats,
-            "Initial health",
-        );
-
-        spawn_stat_input(
-            parent,
-            &button_colors,
-            &fonts,
-            StatId::Dexterity,
-            &player_stats,
-            "Movement",
-        );
-
-        spawn_stat_input(
-            parent,
-            &button_colors,
-            &fonts,
-            StatId::Strength,
-            &player_stats,
-            "Attack damage",
-        );
-
-        spawn_stat_input(
-            parent,
-            &button_colors,
-            &fonts,
-            StatId::Fortitude,
-            &player_stats,
-            "Damage absorb",
-        );
+        for statid in StatId::ALL {
+            spawn_stat_input(parent, &button_colors, &fonts, statid, &player_stats);
+        }
 
-        spawn_stat_input(
-            parent,
-            &button_colors,
-            &fonts,
-            StatId::Aggression,
-            &player_stats,
-            "Chance to move towards enemy",
-        );
+        spawn_spacer(parent);
 
-        spawn_stat_input(
+        spawn_ok_action(
             parent,
             &button_colors,
             &fonts,
-            StatId::Intellect,
-            &player_stats,
-            "Chance to move towards food",
+            "Run",
+            player_stats.points() == 0,
         );
-
-        spawn_spacer(parent);
-
-        spawn_ok_action(parent, &button_colors, &fonts, "Run", false);
     });
 }
 
@@ -0,0 +1,153 @@
+//! Replay state systems
+
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContext};
+
+use super::game::{clock_controls, spawn_hud, spawn_world, sync_world};
+use super::*;
+
+use crate::bundles::*;
+use crate::components::automata::*;
+use crate::components::gridworld::*;
+use crate::events::game::*;
+use crate::game::gridworld::*;
+use crate::game::replay::*;
+use crate::resources::automata::*;
+use crate::resources::game::*;
+use crate::resources::replay::*;
+use crate::resources::ui::*;
+
+/// Replay setup
+pub fn setup(
+    mut commands: Commands,
+    replay: Res<Replay>,
+    mut clock: ResMut<SimulationClock>,
+    colors: Res<AutomataColors>,
+    fonts: Res<Fonts>,
+) {
+    info!(
+        "Playing back round {} replay ({} turns)",
+        replay.round + 1,
+        replay.turns.len()
+    );
+
+    let gridworld = GridWorld::new(replay.width, replay.height);
+    spawn_world(&mut commands, &gridworld, &colors);
+    commands.insert_resource(gridworld);
+
+    let playback = ReplayPlayback::new(&replay);
+    let frame = playback.current();
+
+    // automata
+    let parent = commands
+        .spawn_bundle(EmptyBundle::default())
+        .insert(Name::new("Automata"))
+        .id();
+
+    Automata::spawn_player(&mut commands, parent, colors.player_automata, &frame.player);
+    Automata::spawn_ai(&mut commands, parent, colors.ai_automata, &frame.ai);
+
+    // HUD UI
+    spawn_hud(
+        &mut commands,
+        &fonts,
+        replay.round,
+        frame.player.health,
+        frame.ai.health,
+        true,
+    );
+
+    commands.insert_resource(playback);
+
+    clock.reset();
+    clock.paused = false;
+}
+
+/// Replay playback
+///
+/// Advances a frame for every turn the simulation clock advances
+/// and updates the automata and food to match the frame
+pub fn playback(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut clock: ResMut<SimulationClock>,
+    mut playback: ResMut<ReplayPlayback>,
+    colors: Res<AutomataColors>,
+    mut player_automata_query: Query<
+        (&mut Automata, &mut Transform),
+        (With<PlayerAutomata>, Without<AIAutomata>),
+    >,
+    mut ai_automata_query: Query<
+        (&mut Automata, &mut Transform),
+        (With<AIAutomata>, Without<PlayerAutomata>),
+    >,
+    food_query: Query<(Entity, &Food)>,
+    mut health_changed_events: EventWriter<HealthChangedEvent>,
+) {
+    let turns = clock.tick(time.delta_seconds());
+    playback.frame = (playback.frame + turns).min(playback.last());
+
+    let frame = playback.current();
+
+    sync_world(
+        &mut commands,
+        &frame.player,
+        &frame.ai,
+        &frame.food,
+        &colors,
+        &mut player_automata_query,
+        &mut ai_automata_query,
+        &food_query,
+        &mut health_changed_events,
+    );
+}
+
+/// Replay controls UI
+pub fn replay_controls_ui(
+    mut clock: ResMut<SimulationClock>,
+    mut playback: ResMut<ReplayPlayback>,
+    mut context: ResMut<EguiContext>,
+    mut state: ResMut<State<GameState>>,
+) {
+    let last = playback.last();
+
+    egui::Window::new("Replay")
+        .anchor(egui::Align2::CENTER_BOTTOM, [0.0, -5.0])
+        .collapsible(false)
+        .resizable(false)
+        .show(context.ctx_mut(), |ui| {
+            ui.horizontal(|ui| {
+                let paused = clock_controls(ui, &mut clock);
+
+                if ui.add_enabled(paused, egui::Button::new("<")).clicked() {
+                    playback.frame = playback.frame.saturating_sub(1);
+                }
+
+                if ui.add_enabled(paused, egui::Button::new(">")).clicked() {
+                    playback.frame = (playback.frame + 1).min(last);
+                }
+
+                ui.separator();
+
+                ui.add(egui::Slider::new(&mut playback.frame, 0..=last).text("Turn"));
+
+                ui.separator();
+
+                if ui.button("Exit").clicked() {
+                    state.set(GameState::Intro).unwrap();
+                }
+            });
+        });
+}
+
+/// Replay teardown
+pub fn teardown(mut commands: Commands, entities: Query<Entity>) {
+    for entity in entities.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+
+    commands.remove_resource::<ReplayPlayback>();
+    commands.remove_resource::<GridWorld>();
+
+    commands.remove_resource::<ClearColor>();
+}
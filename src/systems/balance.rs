@@ -0,0 +1,37 @@
+//! Balance systems
+
+use bevy::prelude::*;
+
+use crate::game::balance::*;
+use crate::resources::balance::*;
+
+/// Applies the balance asset whenever it changes
+///
+/// Running matches keep the balance they started with
+pub fn balance_asset_event_handler(
+    mut commands: Commands,
+    mut events: EventReader<AssetEvent<Balance>>,
+    assets: Res<Assets<Balance>>,
+    balance_asset: Option<Res<BalanceAsset>>,
+) {
+    let balance_asset = match balance_asset {
+        Some(balance_asset) => balance_asset,
+        None => return,
+    };
+
+    for event in events.iter() {
+        match event {
+            AssetEvent::Created { handle } | AssetEvent::Modified { handle } => {
+                if *handle != balance_asset.handle {
+                    continue;
+                }
+
+                if let Some(balance) = assets.get(handle) {
+                    info!("Applying balance: {:?}", balance);
+                    commands.insert_resource(*balance);
+                }
+            }
+            AssetEvent::Removed { .. } => (),
+        }
+    }
+}
@@ -2,12 +2,18 @@
 
 use bevy::diagnostic::*;
 use bevy::prelude::*;
+use bevy_egui::egui::plot::{Bar, BarChart, Legend, Line, Plot, Value, Values};
 use bevy_egui::{egui, EguiContext};
 use bevy_inspector_egui::WorldInspectorParams;
 
 use crate::events::debug::*;
+use crate::game::stats::*;
+use crate::resources::automata::*;
 use crate::resources::debug::*;
 
+/// Number of bins in each stat histogram
+const HISTOGRAM_BINS: isize = 10;
+
 /// Debug setup
 pub(crate) fn setup_debug(mut commands: Commands) {
     commands.insert_resource(DebugState::default());
@@ -55,11 +61,12 @@ fn fps(diagnostics: &Diagnostics, dt: f64) -> (f64, f64) {
 
 /// Handles the debug UI
 pub fn debug_ui(
-    debug_state: Res<DebugState>,
+    mut debug_state: ResMut<DebugState>,
     mut context: ResMut<EguiContext>,
     mut inspector: ResMut<WorldInspectorParams>,
     time: Res<Time>,
     diagnostics: Res<Diagnostics>,
+    ai_population: Option<Res<AIAutomataPopulation>>,
 ) {
     if !debug_state.enabled {
         return;
@@ -75,9 +82,136 @@ pub fn debug_ui(
                 frame_time * 1000.0
             ));
 
-            if ui.button("Inspector").clicked() {
-                inspector.enabled = !inspector.enabled;
+            if let Some(ai_population) = ai_population {
+                ui.label(format!(
+                    "Generation {}, mutation rate {:.1}%, diversity {:.2}",
+                    ai_population.generation(),
+                    ai_population.mutation_rate() * 100.0,
+                    ai_population.diversity()
+                ));
             }
+
+            ui.horizontal(|ui| {
+                if ui.button("Inspector").clicked() {
+                    inspector.enabled = !inspector.enabled;
+                }
+
+                if ui.button("Evolution").clicked() {
+                    debug_state.evolution = !debug_state.evolution;
+                }
+            });
         });
     });
 }
+
+/// Handles the AI population evolution UI
+pub fn evolution_ui(
+    mut debug_state: ResMut<DebugState>,
+    mut context: ResMut<EguiContext>,
+    ai_population: Option<Res<AIAutomataPopulation>>,
+) {
+    if !debug_state.enabled || !debug_state.evolution {
+        return;
+    }
+
+    let ai_population = match ai_population {
+        Some(ai_population) => ai_population,
+        None => return,
+    };
+
+    let mut open = debug_state.evolution;
+    egui::Window::new("Evolution")
+        .open(&mut open)
+        .show(context.ctx_mut(), |ui| {
+            ui.label(format!(
+                "Generation {}, mutation rate {:.1}%, diversity {:.2}, {} species, {} on the Pareto front",
+                ai_population.generation(),
+                ai_population.mutation_rate() * 100.0,
+                ai_population.diversity(),
+                ai_population.species().len(),
+                ai_population.pareto_front().len()
+            ));
+
+            ui.separator();
+
+            // fitness per generation
+            let history = ai_population.history();
+            let fitness_line = |name: &str, fitness: fn(&GenerationStats) -> f32| {
+                Line::new(Values::from_values_iter(history.iter().map(|stats| {
+                    Value::new(stats.generation as f64, fitness(stats) as f64)
+                })))
+                .name(name)
+            };
+
+            ui.label("Fitness");
+            Plot::new("Fitness")
+                .height(150.0)
+                .legend(Legend::default())
+                .show(ui, |plot_ui| {
+                    plot_ui.line(fitness_line("Best", |stats| stats.best));
+                    plot_ui.line(fitness_line("Mean", |stats| stats.mean));
+                    plot_ui.line(fitness_line("Worst", |stats| stats.worst));
+                });
+
+            ui.separator();
+
+            // stat histograms
+            let bin_width = (ai_population.points() + 1 + HISTOGRAM_BINS - 1) / HISTOGRAM_BINS;
+            ui.horizontal(|ui| {
+                for statid in StatId::ALL {
+                    let mut bins = vec![0; HISTOGRAM_BINS as usize];
+                    for dna in ai_population.population() {
+                        let bin = (dna.genes().value(statid) / bin_width.max(1))
+                            .clamp(0, HISTOGRAM_BINS - 1);
+                        bins[bin as usize] += 1;
+                    }
+
+                    let bars = bins
+                        .into_iter()
+                        .enumerate()
+                        .map(|(bin, count)| {
+                            Bar::new(
+                                (bin as isize * bin_width) as f64 + bin_width as f64 / 2.0,
+                                count as f64,
+                            )
+                            .width(bin_width as f64)
+                        })
+                        .collect();
+
+                    ui.vertical(|ui| {
+                        ui.label(statid.name());
+                        Plot::new(format!("{} Histogram", statid.name()))
+                            .width(120.0)
+                            .height(80.0)
+                            .show(ui, |plot_ui| {
+                                plot_ui.bar_chart(BarChart::new(bars));
+                            });
+                    });
+                }
+            });
+
+            ui.separator();
+
+            // last evaluated generation table
+            egui::ScrollArea::vertical()
+                .max_height(200.0)
+                .show(ui, |ui| {
+                    egui::Grid::new("Population").striped(true).show(ui, |ui| {
+                        ui.label("Fitness");
+                        for statid in StatId::ALL {
+                            ui.label(statid.name());
+                        }
+                        ui.end_row();
+
+                        for dna in ai_population.evaluated_by_fitness() {
+                            ui.label(format!("{:.3}", dna.total_fitness()));
+                            for statid in StatId::ALL {
+                                ui.label(format!("{}", dna.genes().value(statid)));
+                            }
+                            ui.end_row();
+                        }
+                    });
+                });
+        });
+    debug_state.evolution = open;
+}
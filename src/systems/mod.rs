@@ -1,4 +1,5 @@
 //! ECS systems
 
+pub mod balance;
 pub mod debug;
 pub mod ui;
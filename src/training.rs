@@ -0,0 +1,264 @@
+//! Headless AI population training
+
+use std::error::Error;
+use std::path::Path;
+use std::str::FromStr;
+use std::thread;
+
+use derivative::*;
+
+use crate::game::balance::*;
+use crate::game::persist::*;
+use crate::game::stats::*;
+use crate::options::*;
+use crate::resources::automata::*;
+use crate::resources::*;
+
+/// Number of population members each individual is also matched against
+const TRAINING_OPPONENTS: usize = 3;
+
+/// Default number of islands evolving in parallel
+const DEFAULT_ISLANDS: usize = 4;
+
+/// Default number of generations between island migrations
+const MIGRATION_INTERVAL: usize = 5;
+
+/// Default number of DNA each island sends per migration
+const MIGRANTS: usize = 2;
+
+/// Fixed builds the population is always matched against
+///
+/// A balanced build and a build heavy in each stat
+fn benchmark_builds(points: isize, random: &mut Random) -> Vec<StatSet> {
+    let mut builds = Vec::with_capacity(StatId::ALL.len() + 1);
+
+    let mut balanced = StatSet::default();
+    for statid in StatId::ALL {
+        balanced.set_value(statid, points / StatId::ALL.len() as isize);
+    }
+    balanced.normalize(points, random);
+    builds.push(balanced);
+
+    let heavy = points / 2;
+    let rest = (points - heavy) / (StatId::ALL.len() - 1) as isize;
+    for heavy_statid in StatId::ALL {
+        let mut build = StatSet::default();
+        for statid in StatId::ALL {
+            build.set_value(statid, if statid == heavy_statid { heavy } else { rest });
+        }
+        build.normalize(points, random);
+        builds.push(build);
+    }
+
+    builds
+}
+
+/// Island migration topology
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Derivative)]
+#[derivative(Default)]
+pub enum MigrationTopology {
+    /// Each island sends migrants to the next island
+    #[derivative(Default)]
+    Ring,
+
+    /// Each island sends migrants to every other island
+    Full,
+
+    /// Each island sends migrants to a random other island
+    Random,
+}
+
+impl FromStr for MigrationTopology {
+    type Err = String;
+
+    fn from_str(topology: &str) -> Result<Self, Self::Err> {
+        match topology {
+            "ring" => Ok(Self::Ring),
+            "full" => Ok(Self::Full),
+            "random" => Ok(Self::Random),
+            _ => Err(format!(
+                "Invalid topology '{}', expected ring, full or random",
+                topology
+            )),
+        }
+    }
+}
+
+impl MigrationTopology {
+    /// Gets the islands an island sends migrants to
+    fn destinations(&self, island: usize, islands: usize, random: &mut Random) -> Vec<usize> {
+        if islands < 2 {
+            return vec![];
+        }
+
+        match self {
+            Self::Ring => vec![(island + 1) % islands],
+            Self::Full => (0..islands).filter(|other| *other != island).collect(),
+            Self::Random => {
+                let other = random.random_range(0..islands - 1);
+                vec![if other >= island { other + 1 } else { other }]
+            }
+        }
+    }
+}
+
+/// A population evolving on its own thread
+struct Island {
+    ai_population: AIAutomataPopulation,
+    balance: Balance,
+    random: Random,
+}
+
+impl Island {
+    /// Measures the island population fitness against the benchmark builds
+    /// and a sample of its own members
+    fn evaluate(&mut self, benchmarks: &[StatSet]) {
+        let mut opponents = benchmarks.to_vec();
+        for _ in 0..TRAINING_OPPONENTS {
+            let idx = self
+                .random
+                .random_range(0..self.ai_population.population().len());
+            opponents.push(*self.ai_population.population()[idx].genes());
+        }
+
+        self.ai_population.evaluate_against(
+            &opponents,
+            crate::GRID_WIDTH,
+            crate::GRID_HEIGHT,
+            &self.balance,
+            &mut self.random,
+        );
+    }
+
+    /// Breeds and evaluates a number of generations
+    fn evolve(&mut self, generations: usize, benchmarks: &[StatSet]) {
+        for _ in 0..generations {
+            self.ai_population.next_generation(&mut self.random);
+            self.evaluate(benchmarks);
+        }
+    }
+
+    /// Gets the fittest total fitness
+    fn best_fitness(&self) -> f32 {
+        self.ai_population
+            .population()
+            .iter()
+            .map(|dna| dna.total_fitness())
+            .fold(0.0, f32::max)
+    }
+}
+
+/// Trains the AI population for a number of generations
+///
+/// Islands evolve in parallel, exchanging their fittest DNA
+/// every migration interval. The islands are merged into
+/// a single population that is written to the population file
+pub fn train(options: &Options, generations: usize) -> Result<(), Box<dyn Error>> {
+    let mut random = match options.seed {
+        Some(seed) => Random::new(seed),
+        None => Random::default(),
+    };
+    println!("Training with seed {}", random.seed());
+
+    let island_count = options.islands.unwrap_or(DEFAULT_ISLANDS).max(1);
+    let migration_interval = options
+        .migration_interval
+        .unwrap_or(MIGRATION_INTERVAL)
+        .max(1);
+    let migrants = options.migrants.unwrap_or(MIGRANTS);
+    let topology = options.topology.unwrap_or_default();
+
+    // training has no asset server, so the balance asset is loaded directly
+    let balance_path = Path::new(crate::ASSET_DIR).join(crate::BALANCE_ASSET);
+    let balance = if balance_path.exists() {
+        println!("Loading balance from {}", balance_path.display());
+        Balance::load(&balance_path)?
+    } else {
+        Balance::default()
+    };
+
+    let benchmarks = benchmark_builds(balance.stat_points, &mut random);
+
+    let path = options.population_path();
+    let mut islands = Vec::with_capacity(island_count);
+    for idx in 0..island_count {
+        let mut island_random = Random::new(random.random_range(0..u64::MAX));
+
+        let ai_population = if idx == 0 && path.exists() {
+            println!("Continuing training from {}", path.display());
+            let mut ai_population = AIAutomataPopulation::load(&path)?;
+            ai_population.apply_balance(&balance);
+            ai_population
+        } else {
+            AIAutomataPopulation::new(
+                options.population_config(&balance),
+                balance.rounds,
+                balance.stat_points,
+                &mut island_random,
+            )
+        };
+
+        let mut island = Island {
+            ai_population,
+            balance,
+            random: island_random,
+        };
+        island.evaluate(&benchmarks);
+        islands.push(island);
+    }
+
+    println!(
+        "Training {} island(s), migrating {} every {} generations ({:?})",
+        island_count, migrants, migration_interval, topology
+    );
+
+    let mut generation = 0;
+    while generation < generations {
+        let epoch = migration_interval.min(generations - generation);
+
+        thread::scope(|scope| {
+            for island in islands.iter_mut() {
+                let benchmarks = &benchmarks;
+                scope.spawn(move || island.evolve(epoch, benchmarks));
+            }
+        });
+        generation += epoch;
+
+        for (idx, island) in islands.iter().enumerate() {
+            println!(
+                "Generation {}/{} island {}: best fitness {:.3}, diversity {:.2}",
+                generation,
+                generations,
+                idx,
+                island.best_fitness(),
+                island.ai_population.diversity()
+            );
+        }
+
+        // migrate the fittest DNA between islands
+        let mut incoming = vec![vec![]; islands.len()];
+        for (idx, island) in islands.iter().enumerate() {
+            let emigrants = island.ai_population.emigrants(migrants);
+            for destination in topology.destinations(idx, islands.len(), &mut random) {
+                incoming[destination].extend(emigrants.iter().cloned());
+            }
+        }
+
+        for (island, migrants) in islands.iter_mut().zip(incoming) {
+            island.ai_population.immigrate(migrants);
+        }
+    }
+
+    // merge the fittest of every island into the first
+    let mut islands = islands.into_iter();
+    let mut ai_population = islands.next().unwrap().ai_population;
+    for island in islands {
+        let size = island.ai_population.population().len();
+        ai_population.immigrate(island.ai_population.emigrants(size));
+    }
+
+    ai_population.save(&path)?;
+    println!("Saved trained population to {}", path.display());
+
+    Ok(())
+}